@@ -0,0 +1,214 @@
+//! Builds the feed and the associated outputs for a single playlist
+
+use crate::cache::Cache;
+use crate::compress::Precompress;
+use crate::html::{self, Templates, PAGE_HTML};
+use crate::output::Output;
+use crate::playlists::{self, PLAYLIST_M3U8, PLAYLIST_XSPF};
+use crate::retention::Retention;
+use crate::rss::{AtomLink, Document};
+use crate::subscribers::Subscribers;
+use crate::url::UrlMapper;
+use crate::{meta, paging, rss, websub};
+use feedme_shared::{error, Entry, Error, Playlist};
+use std::path::Path;
+
+/// The build options
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Whether to render an HTML page next to the feed
+    pub html: bool,
+    /// The templates for the HTML page
+    pub templates: Templates,
+    /// Whether to write an extended M3U8 playlist next to the feed
+    pub m3u8: bool,
+    /// Whether to write an XSPF playlist next to the feed
+    pub xspf: bool,
+    /// Whether to skip entries whose media file does not exist instead of failing
+    pub skip_missing: bool,
+    /// The retention policy that limits the items within the feed
+    pub retention: Retention,
+    /// Splits the feed into pages of the given size if set
+    pub page_size: Option<usize>,
+    /// Whether to reuse unchanged feed items from the build cache
+    pub cache: bool,
+    /// The precompressed copies to write next to the feed
+    pub precompress: Precompress,
+    /// The WebSub hub to link and to notify if the feed has changed
+    pub websub_hub: Option<String>,
+    /// Writes one private feed per subscriber instead of the public feed if set
+    pub subscribers: Option<Subscribers>,
+}
+impl Options {
+    /// Ensures that the options can be combined
+    pub fn validate(&self) -> Result<(), Error> {
+        // Subscriber feeds are private, so they must neither be listed nor announced to a hub
+        if self.subscribers.is_some() && (self.html || self.m3u8 || self.xspf) {
+            return Err(error!("subscriber feeds cannot be combined with --html, --m3u8 or --xspf"));
+        }
+        if self.subscribers.is_some() && self.websub_hub.is_some() {
+            return Err(error!(
+                "subscriber feeds cannot be combined with --websub-hub since the hub would learn their URLs"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Builds the feed for the playlist in `dir` and writes it together with all enabled additional outputs
+///
+/// Returns the playlist metadata.
+pub fn build_playlist(dir: &Path, output: &Output, options: &Options, urls: &UrlMapper) -> Result<Playlist, Error> {
+    // Validate the options and load the metadata and skip entries with missing files if requested
+    options.validate()?;
+    let (playlist, mut entries) = meta::collect_metadata(dir)?;
+    if options.skip_missing {
+        entries.retain(|entry| match dir.join(&entry.file) {
+            path if path.exists() => true,
+            path => {
+                eprintln!("[feedme-feed] Skipping entry with missing file: {}", path.display());
+                false
+            }
+        });
+    }
+
+    // Drop the entries that have expired
+    if !options.retention.is_unlimited() {
+        let expired = options.retention.expired(&entries)?;
+        let retained = entries.into_iter().zip(expired).filter(|(_, expired)| !expired);
+        entries = retained.map(|(entry, _)| entry).collect();
+    }
+
+    // Build the public feed or one feed per subscriber
+    let mut cache = options.cache.then(|| Cache::load(dir));
+    let mut feed_updated = false;
+    match &options.subscribers {
+        None => feed_updated = write_feed(dir, output, &playlist, &entries, options, cache.as_mut(), urls)?,
+        Some(subscribers) => {
+            // Build the feeds of the active subscribers
+            let Output::File(feed_path) = output else {
+                return Err(error!("cannot write subscriber feeds to stdout"));
+            };
+            for (name, token) in subscribers.active() {
+                eprintln!("[feedme-feed] Building feed for subscriber: {name}");
+                let output = Output::File(Subscribers::feed_path(feed_path, token));
+                let urls = urls.clone().with_media_token(token.to_string());
+                write_feed(dir, &output, &playlist, &entries, options, cache.as_mut(), &urls)?;
+            }
+
+            // Remove the public feed and the feeds of revoked, removed or rotated tokens
+            subscribers.remove_public(feed_path)?;
+            subscribers.remove_inactive(feed_path)?;
+        }
+    }
+    if let Some(cache) = cache {
+        cache.save(dir)?;
+    }
+
+    // Render the HTML page
+    if options.html {
+        let mut feed_url = None;
+        if let Output::File(feed_path) = output {
+            let feed_url_ = urls.absolute_url(feed_path)?;
+            feed_url = Some(feed_url_);
+        }
+        let page = html::build_page(dir, &playlist, &entries, feed_url.as_deref(), &options.templates, urls)?;
+        Output::File(dir.join(PAGE_HTML)).write(&page)?;
+    }
+
+    // Write the plain playlists
+    if options.m3u8 {
+        let m3u8 = playlists::build_m3u8(dir, &playlist, &entries, urls)?;
+        Output::File(dir.join(PLAYLIST_M3U8)).write(&m3u8)?;
+    }
+    if options.xspf {
+        let xspf = playlists::build_xspf(dir, &playlist, &entries, urls)?;
+        Output::File(dir.join(PLAYLIST_XSPF)).write(&xspf)?;
+    }
+
+    // Announce the update to the WebSub hub once all outputs are in place; the build has succeeded at this point, so a
+    // failed notification is only reported
+    if let (Some(hub), true) = (&options.websub_hub, feed_updated) {
+        let Output::File(feed_path) = output else {
+            return Err(error!("cannot announce a feed written to stdout"));
+        };
+        let feed_url = urls.output_url(feed_path)?;
+        if let Err(e) = websub::publish(hub, &feed_url) {
+            eprintln!("[feedme-feed] Warning: {e}");
+        }
+    }
+    Ok(playlist)
+}
+
+/// Builds the feed and writes it together with its compressed copies to `output`
+///
+/// Returns whether the main feed document has changed.
+fn write_feed(
+    dir: &Path,
+    output: &Output,
+    playlist: &Playlist,
+    entries: &[Entry],
+    options: &Options,
+    mut cache: Option<&mut Cache>,
+    urls: &UrlMapper,
+) -> Result<bool, Error> {
+    // Split the feed into documents
+    let documents = match options.page_size {
+        Some(page_size) => paging::paginate(output, entries, page_size, urls)?,
+        None => {
+            // Link the feed to itself if it is published within the webroot
+            let mut document = Document::new(entries);
+            if let Output::File(feed_path) = output {
+                if let Ok(feed_url) = urls.output_url(feed_path) {
+                    document.links.push(AtomLink::feed("self", feed_url));
+                }
+            }
+            vec![(output.clone(), document)]
+        }
+    };
+
+    // Build the feed documents
+    let mut feeds = Vec::new();
+    for (output, mut document) in documents {
+        // Link the hub and load the previous version
+        if let Some(hub) = &options.websub_hub {
+            document.links.push(AtomLink::hub(hub.clone()));
+        }
+        document.previous = output.read();
+
+        // Build the document
+        let feed = rss::build_feed(dir, playlist, &document, cache.as_deref_mut(), urls)?;
+        feeds.push((output, feed));
+    }
+
+    // Write the feed and its compressed copies; the first document is the main feed
+    let mut feed_updated = false;
+    for (index, (output, feed)) in feeds.into_iter().enumerate() {
+        let updated = output.write(&feed)?;
+        options.precompress.write(&output, &feed)?;
+        feed_updated |= index == 0 && updated;
+    }
+    Ok(feed_updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscribers_with_websub_hub() {
+        let options = Options {
+            subscribers: Some(Subscribers::default()),
+            websub_hub: Some("https://hub.example.org".to_string()),
+            ..Default::default()
+        };
+        let error = options.validate().expect_err("subscriber feeds were announced to a hub");
+        assert!(error.to_string().contains("--websub-hub"));
+
+        // Either option alone is fine
+        let subscribers_only = Options { websub_hub: None, ..options.clone() };
+        subscribers_only.validate().expect("subscriber feeds were rejected");
+        let hub_only = Options { subscribers: None, ..options };
+        hub_only.validate().expect("WebSub hub was rejected");
+    }
+}
@@ -0,0 +1,99 @@
+//! A build cache for feed items
+
+use crate::output::Output;
+use feedme_shared::{Entry, Error};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// The name of the cache file within the playlist directory
+pub const CACHE_FILE: &str = ".feedme-cache";
+
+/// Caches built feed items by a fingerprint of their inputs
+///
+/// The cache file is a JSON object that maps the fingerprints to the serialized items.
+#[derive(Debug, Clone, Default)]
+pub struct Cache {
+    /// The items loaded from the cache file
+    cached: BTreeMap<String, Value>,
+    /// The items that have been used during the current build
+    used: BTreeMap<String, Value>,
+}
+impl Cache {
+    /// Loads the cache from `dir`; a missing or corrupt cache file is treated as empty cache
+    pub fn load(dir: &Path) -> Self {
+        // Read and parse the cache file
+        let cached = fs::read(dir.join(CACHE_FILE)).ok().and_then(|cache| serde_json::from_slice(&cache).ok());
+        Self { cached: cached.unwrap_or_default(), used: BTreeMap::new() }
+    }
+
+    /// Writes all items that have been used during the current build to the cache file in `dir`
+    pub fn save(&self, dir: &Path) -> Result<(), Error> {
+        let cache = serde_json::to_vec(&self.used)?;
+        Output::File(dir.join(CACHE_FILE)).write(&cache)?;
+        Ok(())
+    }
+
+    /// Computes the fingerprint of an entry within `dir`
+    ///
+    /// The fingerprint is a SHA-256 digest over the entry metadata, the size and modification time of the media file
+    /// and the given rendering `context`.
+    pub fn fingerprint<T>(dir: &Path, entry: &Entry, context: &T) -> Result<String, Error>
+    where
+        T: Hash,
+    {
+        // Hash the metadata and the rendering context
+        let mut hasher = DigestHasher(Sha256::new());
+        env!("CARGO_PKG_VERSION").hash(&mut hasher);
+        serde_json::to_string(entry)?.hash(&mut hasher);
+        context.hash(&mut hasher);
+
+        // Hash the media file state; a missing file is not an error here since rendering will fail anyway
+        let metadata = fs::metadata(dir.join(&entry.file)).ok();
+        let modified = metadata.as_ref().and_then(|metadata| metadata.modified().ok());
+        let modified = modified.and_then(|modified| modified.duration_since(UNIX_EPOCH).ok());
+        (metadata.map(|metadata| metadata.len()), modified).hash(&mut hasher);
+        let digest = hasher.0.finalize();
+        Ok(digest.iter().map(|byte| format!("{byte:02x}")).collect())
+    }
+
+    /// Gets the cached item with the given fingerprint or builds it using `build`
+    pub fn get_or_build<T, F>(&mut self, fingerprint: String, build: F) -> Result<T, Error>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Result<T, Error>,
+    {
+        // Get the cached item unless it cannot be deserialized, or build it
+        let cached = self.cached.get(&fingerprint).and_then(|item| serde_json::from_value(item.clone()).ok());
+        let item = match cached {
+            Some(item) => item,
+            None => build()?,
+        };
+
+        // Mark the item as used
+        self.used.insert(fingerprint, serde_json::to_value(&item)?);
+        Ok(item)
+    }
+}
+
+/// A hasher that feeds all hashed values into a SHA-256 digest
+///
+/// Unlike the std hashers, the digest does not change between Rust releases, so the cache stays valid.
+struct DigestHasher(Sha256);
+impl Hasher for DigestHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let digest = self.0.clone().finalize();
+        let (prefix, _) = digest.split_first_chunk().expect("digest is shorter than 8 bytes");
+        u64::from_be_bytes(*prefix)
+    }
+}
@@ -0,0 +1,115 @@
+//! Precompressed copies of the feed for static file servers
+
+use crate::output::Output;
+use brotli::enc::BrotliEncoderParams;
+use feedme_shared::{error, Error};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// The precompressed copies to write next to the feed
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Precompress {
+    /// Whether to write a gzip-compressed copy to `<name>.gz`
+    pub gzip: bool,
+    /// Whether to write a brotli-compressed copy to `<name>.br`
+    pub brotli: bool,
+}
+impl Precompress {
+    /// Whether no compressed copies are written
+    pub fn is_disabled(&self) -> bool {
+        !self.gzip && !self.brotli
+    }
+
+    /// Writes the enabled compressed copies of `data` next to the output file
+    ///
+    /// The copies are always encoded again so that stale or foreign copies are replaced; since the encoding is
+    /// byte-stable, up-to-date copies are left untouched.
+    pub fn write(&self, output: &Output, data: &[u8]) -> Result<(), Error> {
+        // Validate the output
+        if self.is_disabled() {
+            return Ok(());
+        }
+        let Output::File(path) = output else {
+            return Err(error!("cannot write precompressed copies of a feed written to stdout"));
+        };
+
+        // Write the compressed copies
+        if self.gzip {
+            let compressed = gzip(data)?;
+            Output::File(with_suffix(path, "gz")).write(&compressed)?;
+        }
+        if self.brotli {
+            let compressed = brotli(data)?;
+            Output::File(with_suffix(path, "br")).write(&compressed)?;
+        }
+        Ok(())
+    }
+}
+
+/// Appends the suffix to the file name
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_os_string();
+    path.push(".");
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// Compresses the data using gzip; the header contains no timestamp so that the output is byte-stable
+fn gzip(data: &[u8]) -> Result<Vec<u8>, io::Error> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+/// Compresses the data using brotli
+fn brotli(data: &[u8]) -> Result<Vec<u8>, io::Error> {
+    let mut compressed = Vec::new();
+    let params = BrotliEncoderParams { quality: 11, ..Default::default() };
+    brotli::BrotliCompress(&mut &data[..], &mut compressed, &params)?;
+    Ok(compressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+    use flate2::read::GzDecoder;
+    use std::fs;
+    use std::io::Read;
+
+    /// Decompresses a gzip file
+    fn gunzip(path: &Path) -> Vec<u8> {
+        let mut data = Vec::new();
+        let file = fs::File::open(path).expect("failed to open gzip copy");
+        GzDecoder::new(file).read_to_end(&mut data).expect("invalid gzip copy");
+        data
+    }
+
+    /// Decompresses a brotli file
+    fn unbrotli(path: &Path) -> Vec<u8> {
+        let mut data = Vec::new();
+        let file = fs::File::open(path).expect("failed to open brotli copy");
+        brotli::BrotliDecompress(&mut io::BufReader::new(file), &mut data).expect("invalid brotli copy");
+        data
+    }
+
+    #[test]
+    fn stale_copies() {
+        // Write the feed without updating the compressed copies, e.g. after a failed or disabled precompression
+        let dir = TempDir::new("compress-stale_copies");
+        let feed_path = dir.path().join("feed.rss");
+        let output = Output::File(feed_path.clone());
+        let precompress = Precompress { gzip: true, brotli: true };
+        precompress.write(&output, b"<rss>old</rss>").expect("failed to write compressed copies");
+        output.write(b"<rss>new</rss>").expect("failed to write feed");
+
+        // The compressed copies must follow the feed even though the feed itself is already up to date
+        let updated = output.write(b"<rss>new</rss>").expect("failed to write feed");
+        assert!(!updated);
+        precompress.write(&output, b"<rss>new</rss>").expect("failed to write compressed copies");
+        assert_eq!(gunzip(&with_suffix(&feed_path, "gz")), b"<rss>new</rss>");
+        assert_eq!(unbrotli(&with_suffix(&feed_path, "br")), b"<rss>new</rss>");
+    }
+}
@@ -0,0 +1,182 @@
+//! A static HTML episode page
+
+use crate::notes;
+use crate::url::UrlMapper;
+use feedme_shared::{error, Entry, Error, Playlist};
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use time::format_description::FormatItem;
+use time::macros::format_description;
+use time::OffsetDateTime;
+
+/// The name of the generated HTML page
+pub const PAGE_HTML: &str = "index.html";
+
+/// The HTML templates
+#[derive(Debug, Clone)]
+pub struct Templates {
+    /// The page template
+    pub page: String,
+    /// The template for a single episode
+    pub episode: String,
+}
+impl Templates {
+    /// The name of the page template file
+    pub const PAGE: &'static str = "page.html";
+    /// The name of the episode template file
+    pub const EPISODE: &'static str = "episode.html";
+
+    /// Loads the templates from the given directory, falling back to the built-in templates for missing files
+    pub fn load(dir: &Path) -> Result<Self, Error> {
+        let mut templates = Self::default();
+        for (name, template) in [(Self::PAGE, &mut templates.page), (Self::EPISODE, &mut templates.episode)] {
+            // Read the template if it exists
+            let path = dir.join(name);
+            match fs::read_to_string(&path) {
+                Ok(string) => *template = string,
+                Err(e) if e.kind() == ErrorKind::NotFound => (),
+                Err(e) => return Err(error!(with: e, r#"Failed to open "{}""#, path.display())),
+            }
+        }
+        Ok(templates)
+    }
+}
+impl Default for Templates {
+    fn default() -> Self {
+        let page = include_str!("../templates/page.html").to_string();
+        let episode = include_str!("../templates/episode.html").to_string();
+        Self { page, episode }
+    }
+}
+
+/// Renders an HTML page for the playlist in `dir`
+pub fn build_page(
+    dir: &Path,
+    playlist: &Playlist,
+    entries: &[Entry],
+    feed_url: Option<&str>,
+    templates: &Templates,
+    urls: &UrlMapper,
+) -> Result<Vec<u8>, Error> {
+    // Render the episodes
+    let mut episodes = String::new();
+    for (index, entry) in entries.iter().enumerate() {
+        // Select the player element
+        let player = match entry.type_.starts_with("audio/") {
+            true => "audio",
+            false => "video",
+        };
+
+        // Collect the values
+        let url = urls.media_url(dir, entry)?;
+        let description = entry.description.as_deref().map(|description| notes::render_text(description, entry.markup));
+        let values = HashMap::from([
+            ("episode", (index + 1).to_string()),
+            ("title", escape(&entry.title)),
+            ("description", escape(description.as_deref().unwrap_or_default())),
+            ("url", escape(&url)),
+            ("type", escape(&entry.type_)),
+            ("player", player.to_string()),
+            ("duration", format_duration(entry.duration)),
+            ("date", format_date(entry.date)?),
+        ]);
+        episodes.push_str(&render(&templates.episode, &values)?);
+    }
+
+    // Get the thumbnail URL
+    let mut thumbnail = String::new();
+    if let Some(thumbnail_) = &playlist.thumbnail {
+        thumbnail = urls.absolute_url(&dir.join(thumbnail_))?;
+    }
+
+    // Render the page
+    let values = HashMap::from([
+        ("title", escape(&playlist.title)),
+        ("description", escape(playlist.description.as_deref().unwrap_or_default())),
+        ("author", escape(playlist.author.as_deref().unwrap_or_default())),
+        ("link", escape(playlist.url.as_deref().unwrap_or_default())),
+        ("thumbnail", escape(&thumbnail)),
+        ("feed_url", escape(feed_url.unwrap_or_default())),
+        ("episodes", episodes),
+    ]);
+    let page = render(&templates.page, &values)?;
+    Ok(page.into_bytes())
+}
+
+/// Renders a template by replacing all `{{key}}` placeholders with their values
+///
+/// A section `{{#key}}...{{/key}}` is only rendered if the value for `key` is not empty.
+fn render(template: &str, values: &HashMap<&str, String>) -> Result<String, Error> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(tag_start) = rest.find("{{") {
+        // Copy the text before the tag
+        rendered.push_str(&rest[..tag_start]);
+        rest = &rest[tag_start + 2..];
+
+        // Get the tag
+        let Some(tag_end) = rest.find("}}") else {
+            return Err(error!("unterminated template tag"));
+        };
+        let tag = rest[..tag_end].trim();
+        rest = &rest[tag_end + 2..];
+
+        // Render the tag
+        if let Some(key) = tag.strip_prefix('#') {
+            // Find the end of the section
+            let section_close = format!("{{{{/{key}}}}}");
+            let Some(section_end) = rest.find(&section_close) else {
+                return Err(error!("unterminated template section: {key}"));
+            };
+            let section = &rest[..section_end];
+            rest = &rest[section_end + section_close.len()..];
+
+            // Render the section if the value is not empty
+            if values.get(key).is_some_and(|value| !value.is_empty()) {
+                rendered.push_str(&render(section, values)?);
+            }
+        } else {
+            let value = values.get(tag).map(String::as_str).unwrap_or_default();
+            rendered.push_str(value);
+        }
+    }
+
+    // Copy the remaining text
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// Formats a duration in seconds as `H:MM:SS`
+pub fn format_duration(duration: u64) -> String {
+    let (hours, minutes, seconds) = (duration / 3600, (duration / 60) % 60, duration % 60);
+    format!("{hours}:{minutes:02}:{seconds:02}")
+}
+
+/// Formats a unix timestamp as `YYYY-MM-DD`
+fn format_date(timestamp: u64) -> Result<String, Error> {
+    /// The date format within the page
+    const DATE_FORMAT: &[FormatItem] = format_description!("[year]-[month]-[day]");
+
+    // Format the date
+    let timestamp = i64::try_from(timestamp).map_err(|e| error!(with: e, "timestamp is too large"))?;
+    let date = OffsetDateTime::from_unix_timestamp(timestamp)?;
+    Ok(date.format(DATE_FORMAT)?)
+}
+
+/// Escapes a string for use as HTML text or attribute value
+pub fn escape(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+    for char in string.chars() {
+        match char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            char => escaped.push(char),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,63 @@
+//! An HTML listing of generated feeds
+
+use crate::html::escape;
+use crate::url::UrlMapper;
+use feedme_shared::{Error, Playlist};
+use std::fmt::Write;
+use std::path::Path;
+
+/// A generated feed
+#[derive(Debug, Clone)]
+pub struct FeedInfo {
+    /// The human readable playlist title
+    pub title: String,
+    /// The playlist description
+    pub description: Option<String>,
+    /// The URL to the show
+    pub link: Option<String>,
+    /// The absolute URL of the generated feed
+    pub url: String,
+}
+impl FeedInfo {
+    /// Creates the feed info for a playlist whose feed has been written to `feed_path`
+    pub fn new(playlist: &Playlist, feed_path: &Path, urls: &UrlMapper) -> Result<Self, Error> {
+        let url = urls.absolute_url(feed_path)?;
+        Ok(Self {
+            title: playlist.title.clone(),
+            description: playlist.description.clone(),
+            link: playlist.url.clone(),
+            url,
+        })
+    }
+}
+
+/// Renders an HTML page listing all given feeds
+pub fn build_html(feeds: &[FeedInfo]) -> Vec<u8> {
+    // Write the header
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n");
+    html.push_str("<html>\n");
+    html.push_str("<head>\n");
+    html.push_str("  <meta charset=\"utf-8\">\n");
+    html.push_str("  <title>Feeds</title>\n");
+    html.push_str("</head>\n");
+    html.push_str("<body>\n");
+    html.push_str("  <h1>Feeds</h1>\n");
+    html.push_str("  <ul>\n");
+
+    // Write the feeds
+    for feed in feeds {
+        let (url, title) = (escape(&feed.url), escape(&feed.title));
+        let _ = write!(html, "    <li><a href=\"{url}\">{title}</a>");
+        if let Some(description) = &feed.description {
+            let _ = write!(html, "<p>{}</p>", escape(description));
+        }
+        html.push_str("</li>\n");
+    }
+
+    // Write the footer
+    html.push_str("  </ul>\n");
+    html.push_str("</body>\n");
+    html.push_str("</html>\n");
+    html.into_bytes()
+}
@@ -0,0 +1,23 @@
+#![doc = include_str!("../README.md")]
+
+pub mod build;
+pub mod cache;
+pub mod compress;
+pub mod html;
+pub mod index;
+pub mod meta;
+pub mod multi;
+pub mod notes;
+pub mod opml;
+pub mod output;
+pub mod paging;
+pub mod playlists;
+pub mod retention;
+pub mod rss;
+pub mod signing;
+pub mod subscribers;
+pub mod url;
+pub mod websub;
+
+#[cfg(test)]
+mod testutil;
@@ -1,8 +1,19 @@
 #![doc = include_str!("../README.md")]
 
-mod rss;
-
-use feedme_shared::{error, Error};
+use feedme_feed::build::{self, Options};
+use feedme_feed::compress::Precompress;
+use feedme_feed::html::Templates;
+use feedme_feed::index::FeedInfo;
+use feedme_feed::output::Output;
+use feedme_feed::retention::Retention;
+use feedme_feed::signing::{self, Signer};
+use feedme_feed::subscribers::Subscribers;
+use feedme_feed::url::UrlMapper;
+use feedme_feed::{meta, multi, opml};
+use feedme_shared::{error, Error, Watcher};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{env, process};
 
 /// Displays the error and exits with status `2`
@@ -22,18 +33,176 @@ fn exit_error(e: Error) -> ! {
     process::exit(1);
 }
 
+/// Parses the command line arguments into a key-value map
+fn parse_args() -> Result<HashMap<String, String>, Error> {
+    let mut args = HashMap::new();
+    let mut argv = env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        // Check if we have a key-value arg
+        if let Some(kv_arg) = arg.strip_prefix("--") {
+            // Split argument
+            let (key, value) = kv_arg.split_once('=').unwrap_or((kv_arg, ""));
+            args.insert(key.to_string(), value.to_string());
+        } else if arg == "-o" {
+            // Short form of `--output=`
+            let value = argv.next().ok_or(error!("missing value for argument: -o"))?;
+            args.insert("output".to_string(), value);
+        } else {
+            return Err(error!("unexpected argument: {arg}"));
+        }
+    }
+    Ok(args)
+}
+
 /// The fallible, real main function
 fn main_real() -> Result<(), Error> {
-    // Load the required variables from the environment
-    let Ok(base_url) = env::var("FEEDME_BASE_URL") else {
-        return Err(error!("missing FEEDME_BASE_URL environment variable"));
+    // Parse the arguments and verify the URL if requested
+    let mut args = parse_args()?;
+    if let Some(url) = args.remove("verify-url") {
+        return verify_url(&url);
+    }
+    let output = args.remove("output").unwrap_or_else(|| "feed.rss".to_string());
+    let opml = args.remove("opml").map(PathBuf::from);
+    let recursive = args.remove("recursive").is_some();
+    let watch = args.remove("watch").is_some();
+    let lexical_paths = args.remove("lexical-paths").is_some();
+    let media_url = args.remove("media-url");
+    let sign_media = args.remove("sign-media").map(|ttl| ttl.parse()).transpose()?;
+    let retention = Retention {
+        keep: args.remove("keep").map(|keep| keep.parse()).transpose()?,
+        max_age: args.remove("max-age").map(|max_age| max_age.parse()).transpose()?,
     };
-    let Ok(webroot) = env::var("FEEDME_WEBROOT") else {
-        return Err(error!("missing FEEDME_WEBROOT environment variable"));
+    let mut options = Options {
+        html: args.remove("html").is_some(),
+        m3u8: args.remove("m3u8").is_some(),
+        xspf: args.remove("xspf").is_some(),
+        skip_missing: args.remove("skip-missing").is_some(),
+        retention,
+        page_size: args.remove("page-size").map(|page_size| page_size.parse()).transpose()?,
+        cache: args.remove("cache").is_some(),
+        precompress: Precompress { gzip: args.remove("gzip").is_some(), brotli: args.remove("brotli").is_some() },
+        websub_hub: args.remove("websub-hub"),
+        ..Default::default()
     };
+    if let Some(templates) = args.remove("html-templates") {
+        options.templates = Templates::load(Path::new(&templates))?;
+    }
+    if let Some(subscribers) = args.remove("subscribers") {
+        let revoked = args.remove("revoked").map(PathBuf::from);
+        options.subscribers = Some(Subscribers::load(Path::new(&subscribers), revoked.as_deref())?);
+    }
+    if let Some(arg) = args.keys().next() {
+        return Err(error!("unexpected argument: --{arg}"));
+    }
+    if output == "-" && !options.precompress.is_disabled() {
+        return Err(error!("cannot write precompressed copies of a feed written to stdout"));
+    }
+    if output == "-" && options.websub_hub.is_some() {
+        return Err(error!("cannot announce a feed written to stdout"));
+    }
+    if options.subscribers.is_some() && (recursive || opml.is_some()) {
+        return Err(error!("subscriber feeds cannot be combined with --recursive or --opml"));
+    }
+    options.validate()?;
+
+    // Build the feeds
+    if !watch {
+        let urls = load_urls(lexical_paths, media_url.as_deref(), sign_media)?;
+        return build(recursive, &output, opml.as_deref(), &options, &urls);
+    }
+    if output == "-" {
+        return Err(error!("cannot watch for changes while writing the feed to stdout"));
+    }
+
+    // Rebuild the feeds whenever the metadata or the media files change
+    loop {
+        // Watch all directories that may contain a playlist and the directories of the referenced media files
+        let mut dirs = match recursive {
+            true => multi::find_dirs(Path::new("."))?,
+            false => BTreeSet::from([PathBuf::from(".")]),
+        };
+        let media = media_files(&dirs);
+        let media_dirs = media.iter().filter_map(|file| file.parent()).filter(|dir| dir.is_dir());
+        dirs.extend(media_dirs.map(Path::to_path_buf));
+        let mut watcher = Watcher::new(dirs)?;
+
+        // Build the feeds and wait for changes
+        let result = load_urls(lexical_paths, media_url.as_deref(), sign_media)
+            .and_then(|urls| build(recursive, &output, opml.as_deref(), &options, &urls));
+        if let Err(e) = result {
+            eprintln!("[feedme-feed] Failed to build feed: {e}");
+        }
+
+        // Wait for changes to the metadata or the media files
+        let media_names: BTreeSet<_> = media.iter().filter_map(|file| file.file_name()?.to_str()).collect();
+        watcher.wait(|name| name.ends_with(".feedme") || media_names.contains(name))?;
+    }
+}
+
+/// Collects the paths of the media files that are referenced by the playlists within the directories
+fn media_files(dirs: &BTreeSet<PathBuf>) -> BTreeSet<PathBuf> {
+    let mut files = BTreeSet::new();
+    'dirs: for dir in dirs {
+        // Load the playlist or skip the directory
+        let Ok((_, entries)) = meta::collect_metadata(dir) else {
+            continue 'dirs;
+        };
+        files.extend(entries.iter().map(|entry| dir.join(&entry.file)));
+    }
+    files
+}
 
-    // Canonicalize metadata
-    rss::build_feed(&base_url, &webroot)
+/// Loads the URL mapping from the environment and applies the path mode, media URL template and signing lifetime
+fn load_urls(lexical_paths: bool, media_url: Option<&str>, sign_media: Option<u64>) -> Result<UrlMapper, Error> {
+    let mut urls = UrlMapper::from_env()?;
+    if lexical_paths {
+        urls = urls.with_lexical_paths();
+    }
+    if let Some(media_url) = media_url {
+        urls = urls.with_media_template(media_url.to_string());
+    }
+    if let Some(ttl) = sign_media {
+        let signer = Signer::from_env(ttl)?;
+        urls = urls.with_media_signer(signer);
+    }
+    Ok(urls)
+}
+
+/// Verifies a signed URL using the key from the environment
+fn verify_url(url: &str) -> Result<(), Error> {
+    let key = signing::load_key()?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    match signing::verify(&key, url, now) {
+        true => Ok(()),
+        false => Err(error!("invalid or expired signature: {url}")),
+    }
+}
+
+/// Builds the feed for the current directory, or all feeds below the current directory if `recursive` is set
+fn build(recursive: bool, output: &str, opml: Option<&Path>, options: &Options, urls: &UrlMapper) -> Result<(), Error> {
+    // Build all feeds below the current directory
+    if recursive {
+        if output == "-" {
+            return Err(error!("cannot write multiple feeds to stdout"));
+        }
+        return multi::build_feeds(Path::new("."), output, opml, options, urls);
+    }
+
+    // Build and write the feed
+    let output = Output::from(output.to_string());
+    let urls = urls.for_playlist(Path::new("."))?;
+    let playlist = build::build_playlist(Path::new("."), &output, options, &urls)?;
+
+    // Write the OPML subscription list if requested
+    if let Some(opml_path) = opml {
+        let Output::File(feed_path) = output else {
+            return Err(error!("cannot create an OPML subscription list for a feed written to stdout"));
+        };
+        let feed_info = FeedInfo::new(&playlist, &feed_path, &urls)?;
+        let opml = opml::build_opml(&[feed_info])?;
+        Output::File(opml_path.to_path_buf()).write(&opml)?;
+    }
+    Ok(())
 }
 
 fn main() {
@@ -0,0 +1,63 @@
+//! Loads the canonicalized feedme metadata
+
+use feedme_shared::{error, Entry, Error, Playlist};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+/// The name of the playlist metadata file
+pub const PLAYLIST_META: &str = "playlist-meta.feedme";
+
+/// Collect all metadata files within the given directory
+pub fn collect_metadata(dir: &Path) -> Result<(Playlist, Vec<Entry>), Error> {
+    // Read the playlist
+    let playlist_path = dir.join(PLAYLIST_META);
+    let playlist_bin =
+        fs::read(&playlist_path).map_err(|e| error!(with: e, r#"Failed to open "{}""#, playlist_path.display()))?;
+    let playlist = serde_json::from_slice(&playlist_bin)?;
+
+    // Collect the entries
+    let entries = collect_entries(dir)?.into_values().collect();
+    Ok((playlist, entries))
+}
+
+/// Collects all playlist entries within the given directory, ordered by the name of their metadata file
+pub fn collect_entries(dir: &Path) -> Result<BTreeMap<String, Entry>, Error> {
+    // List all entry files and sort them
+    let mut entry_names = BTreeSet::new();
+    'list_dir: for file in fs::read_dir(dir)? {
+        // Unwrap the entry or skip it
+        let Ok(file) = file else {
+            continue 'list_dir;
+        };
+
+        // Get the filename and check that it references a feedme file
+        let file_name_os = file.file_name();
+        let Some(file_name) = file_name_os.to_str() else {
+            continue 'list_dir;
+        };
+
+        // Ensure that the file is a playlist entry
+        if !file_name.starts_with("playlist-entry") {
+            continue 'list_dir;
+        }
+        if !file_name.ends_with(".feedme") {
+            continue 'list_dir;
+        }
+
+        // Collect the entry
+        entry_names.insert(file_name.to_string());
+    }
+
+    // Process the entries in order
+    let mut entries = BTreeMap::new();
+    for entry_name in entry_names {
+        // Parse the entry
+        let entry_path = dir.join(&entry_name);
+        let entry_bin =
+            fs::read(&entry_path).map_err(|e| error!(with: e, r#"Failed to open "{}""#, entry_path.display()))?;
+        let entry = serde_json::from_slice(&entry_bin)?;
+        entries.insert(entry_name, entry);
+    }
+    Ok(entries)
+}
@@ -0,0 +1,89 @@
+//! Recursive multi-feed builds
+
+use crate::build::{self, Options};
+use crate::index::{self, FeedInfo};
+use crate::meta::PLAYLIST_META;
+use crate::opml;
+use crate::output::Output;
+use crate::url::UrlMapper;
+use feedme_shared::Error;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The name of the generated OPML index
+pub const INDEX_OPML: &str = "feeds.opml";
+/// The name of the generated HTML index
+pub const INDEX_HTML: &str = "feeds.html";
+
+/// Builds a feed named `output` for every playlist below `root` and writes an OPML and HTML index into `root`
+///
+/// If `opml_path` is set, the OPML index is written to the given path instead.
+pub fn build_feeds(
+    root: &Path,
+    output: &str,
+    opml_path: Option<&Path>,
+    options: &Options,
+    urls: &UrlMapper,
+) -> Result<(), Error> {
+    // Build the feeds
+    let mut feeds = Vec::new();
+    for dir in find_playlists(root)? {
+        // Build the feed
+        eprintln!("[feedme-feed] Building feed for: {}", dir.display());
+        let feed_path = dir.join(output);
+        let urls = urls.for_playlist(&dir)?;
+        let playlist = build::build_playlist(&dir, &Output::File(feed_path.clone()), options, &urls)?;
+
+        // Register the feed
+        let feed_info = FeedInfo::new(&playlist, &feed_path, &urls)?;
+        feeds.push(feed_info);
+    }
+
+    // Write the indices
+    let opml = opml::build_opml(&feeds)?;
+    let opml_path = opml_path.map(Path::to_path_buf).unwrap_or_else(|| root.join(INDEX_OPML));
+    Output::File(opml_path).write(&opml)?;
+    let html = index::build_html(&feeds);
+    Output::File(root.join(INDEX_HTML)).write(&html)?;
+    Ok(())
+}
+
+/// Recursively collects all directories below `root` that contain a playlist
+fn find_playlists(root: &Path) -> Result<BTreeSet<PathBuf>, Error> {
+    let mut dirs = find_dirs(root)?;
+    dirs.retain(|dir| dir.join(PLAYLIST_META).is_file());
+    Ok(dirs)
+}
+
+/// Recursively collects `root` and all non-hidden directories below `root`
+pub fn find_dirs(root: &Path) -> Result<BTreeSet<PathBuf>, Error> {
+    let mut dirs = BTreeSet::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        // Descend into the subdirectories
+        'read_dir: for entry in fs::read_dir(&dir)? {
+            // Unwrap the entry or skip it
+            let Ok(entry) = entry else {
+                continue 'read_dir;
+            };
+
+            // Skip hidden entries
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue 'read_dir;
+            }
+
+            // Only follow real directories to avoid symlink loops
+            let Ok(file_type) = entry.file_type() else {
+                continue 'read_dir;
+            };
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            }
+        }
+
+        // Register the directory
+        dirs.insert(dir);
+    }
+    Ok(dirs)
+}
@@ -0,0 +1,154 @@
+//! HTML and plain text show notes rendered from the entry descriptions
+
+use crate::html;
+use feedme_shared::{Chapter, Entry, Markup};
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use regex::Regex;
+use std::mem;
+use std::sync::OnceLock;
+
+/// Matches the URLs and timestamps within a plain text description
+fn link_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        // Match HTTP(S) URLs and `[h:]mm:ss` timestamps
+        Regex::new(r#"https?://[^\s<>"]+|\b(?:\d{1,2}:)?\d{1,2}:\d{2}\b"#).expect("invalid link pattern")
+    })
+}
+
+/// Renders the description and the chapter markers of the entry as HTML show notes
+///
+/// Markdown descriptions are converted to HTML. Plain text descriptions are escaped, split into paragraphs, and their
+/// URLs and timestamps are turned into links; timestamps link into the media file at `media_url`. The chapters are
+/// appended as list of timestamp links unless the description already lists them. Returns `None` if there is neither a
+/// description nor any chapter.
+pub fn render_html(entry: &Entry, media_url: &str) -> Option<String> {
+    // Render the description
+    let mut html = match (entry.description.as_deref(), entry.markup) {
+        (None, _) => String::new(),
+        (Some(description), Markup::Markdown) => markdown_to_html(description),
+        (Some(description), Markup::Plain) => plain_to_html(description, media_url),
+    };
+
+    // Append the chapters if they are not part of the description
+    let description_chapters = entry.description.as_deref().map(Chapter::from_description).unwrap_or_default();
+    if !entry.chapters.is_empty() && description_chapters.is_empty() {
+        html.push_str(&chapters_to_html(&entry.chapters, media_url));
+    }
+
+    // Return the notes if any
+    let html = html.trim_end();
+    (!html.is_empty()).then(|| html.to_string())
+}
+
+/// Renders the description as plain text
+///
+/// Plain text descriptions are returned as-is; Markdown descriptions are stripped of their formatting.
+pub fn render_text(description: &str, markup: Markup) -> String {
+    match markup {
+        Markup::Markdown => markdown_to_text(description),
+        Markup::Plain => description.to_string(),
+    }
+}
+
+/// Converts Markdown to HTML
+fn markdown_to_html(markdown: &str) -> String {
+    let mut rendered = String::new();
+    pulldown_cmark::html::push_html(&mut rendered, Parser::new(markdown));
+    rendered
+}
+
+/// Converts Markdown to plain text by keeping the text content and the link targets
+fn markdown_to_text(markdown: &str) -> String {
+    let mut text = String::new();
+    let mut links = Vec::new();
+    for event in Parser::new(markdown) {
+        match event {
+            // Keep the text content
+            Event::Text(string) | Event::Code(string) => text.push_str(&string),
+            Event::SoftBreak | Event::HardBreak => text.push('\n'),
+
+            // Append the link targets after the link text
+            Event::Start(Tag::Link { dest_url, .. }) => links.push(dest_url),
+            Event::End(TagEnd::Link) => {
+                if let Some(dest_url) = links.pop() {
+                    text.push_str(&format!(" ({dest_url})"));
+                }
+            }
+
+            // Separate the blocks
+            Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::CodeBlock) => text.push_str("\n\n"),
+            Event::End(TagEnd::Item) => text.push('\n'),
+            _ => (),
+        }
+    }
+    text.trim_end().to_string()
+}
+
+/// Converts plain text to HTML paragraphs with linked URLs and timestamps
+fn plain_to_html(text: &str, media_url: &str) -> String {
+    // Group the lines into paragraphs that are separated by blank lines
+    let mut paragraphs: Vec<Vec<&str>> = Vec::new();
+    let mut paragraph = Vec::new();
+    for line in text.lines() {
+        match line.trim().is_empty() {
+            true if !paragraph.is_empty() => paragraphs.push(mem::take(&mut paragraph)),
+            true => (),
+            false => paragraph.push(line),
+        }
+    }
+    if !paragraph.is_empty() {
+        paragraphs.push(paragraph);
+    }
+
+    // Render the paragraphs
+    let mut rendered = String::new();
+    for paragraph in paragraphs {
+        let lines: Vec<_> = paragraph.into_iter().map(|line| linkify(line, media_url)).collect();
+        rendered.push_str(&format!("<p>{}</p>\n", lines.join("<br>\n")));
+    }
+    rendered
+}
+
+/// Escapes a line of plain text and turns its URLs and timestamps into links
+fn linkify(line: &str, media_url: &str) -> String {
+    let mut rendered = String::new();
+    let mut offset = 0;
+    for link in link_pattern().find_iter(line) {
+        // Create the link target; URLs lose trailing punctuation and invalid timestamps remain text
+        let (text, href) = match link.as_str() {
+            url if url.starts_with("http") => {
+                let url = url.trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '}', '\'']);
+                (url, url.to_string())
+            }
+            timestamp => match Chapter::parse_timestamp(timestamp) {
+                Some(seconds) => (timestamp, format!("{media_url}#t={seconds}")),
+                None => continue,
+            },
+        };
+
+        // Write the preceding text and the link
+        let end = link.start() + text.len();
+        rendered.push_str(&html::escape(&line[offset..link.start()]));
+        rendered.push_str(&format!(r#"<a href="{}">{}</a>"#, html::escape(&href), html::escape(text)));
+        offset = end;
+    }
+
+    // Write the remaining text
+    rendered.push_str(&html::escape(&line[offset..]));
+    rendered
+}
+
+/// Renders the chapters as list of timestamp links
+fn chapters_to_html(chapters: &[Chapter], media_url: &str) -> String {
+    let mut rendered = String::from("<ul>\n");
+    for chapter in chapters {
+        // Link the chapter start
+        let href = html::escape(&format!("{media_url}#t={}", chapter.start));
+        let timestamp = html::format_duration(chapter.start);
+        rendered.push_str(&format!(r#"<li><a href="{href}">{timestamp}</a> {}</li>"#, html::escape(&chapter.title)));
+        rendered.push('\n');
+    }
+    rendered.push_str("</ul>\n");
+    rendered
+}
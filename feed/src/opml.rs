@@ -0,0 +1,45 @@
+//! An OPML subscription list
+
+use crate::index::FeedInfo;
+use feedme_shared::Error;
+use xml::writer::XmlEvent;
+use xml::{EmitterConfig, EventWriter};
+
+/// Builds an OPML subscription list containing all given feeds
+pub fn build_opml(feeds: &[FeedInfo]) -> Result<Vec<u8>, Error> {
+    // Create the writer
+    let writer_config = EmitterConfig::new().perform_indent(true);
+    let mut writer = EventWriter::new_with_config(Vec::new(), writer_config);
+
+    // Write the header
+    writer.write(XmlEvent::start_element("opml").attr("version", "2.0"))?;
+    writer.write(XmlEvent::start_element("head"))?;
+    writer.write(XmlEvent::start_element("title"))?;
+    writer.write(XmlEvent::characters("Feeds"))?;
+    writer.write(XmlEvent::end_element())?;
+    writer.write(XmlEvent::end_element())?;
+
+    // Write the feeds
+    writer.write(XmlEvent::start_element("body"))?;
+    for feed in feeds {
+        let mut outline = XmlEvent::start_element("outline")
+            .attr("type", "rss")
+            .attr("version", "RSS2")
+            .attr("text", &feed.title)
+            .attr("title", &feed.title)
+            .attr("xmlUrl", &feed.url);
+        if let Some(link) = &feed.link {
+            outline = outline.attr("htmlUrl", link);
+        }
+        if let Some(description) = &feed.description {
+            outline = outline.attr("description", description);
+        }
+        writer.write(outline)?;
+        writer.write(XmlEvent::end_element())?;
+    }
+    writer.write(XmlEvent::end_element())?;
+
+    // Close the document
+    writer.write(XmlEvent::end_element())?;
+    Ok(writer.into_inner())
+}
@@ -0,0 +1,82 @@
+//! Feed output targets
+
+use feedme_shared::{error, Error};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// An output target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    /// Writes the data to stdout
+    Stdout,
+    /// Atomically writes the data to the given file unless the file already has the same contents
+    File(PathBuf),
+}
+impl Output {
+    /// Writes the data to the output target
+    ///
+    /// Returns whether the data has been written, i.e. `false` if the file was already up to date.
+    pub fn write(&self, data: &[u8]) -> Result<bool, Error> {
+        match self {
+            Self::Stdout => Self::write_stdout(data),
+            Self::File(path) => Self::write_file(path, data),
+        }
+    }
+
+    /// Reads the current contents of the output target if any
+    pub fn read(&self) -> Option<Vec<u8>> {
+        match self {
+            Self::Stdout => None,
+            Self::File(path) => fs::read(path).ok(),
+        }
+    }
+
+    /// Writes the data to stdout
+    fn write_stdout(data: &[u8]) -> Result<bool, Error> {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(data)?;
+        stdout.flush()?;
+        Ok(true)
+    }
+
+    /// Writes the data to a temporary file next to `path` and renames it on success
+    fn write_file(path: &Path, data: &[u8]) -> Result<bool, Error> {
+        // Leave the file untouched if it is up to date
+        if fs::read(path).is_ok_and(|existing| existing == data) {
+            return Ok(false);
+        }
+
+        // Build the path of the temporary file within the target directory
+        let Some(file_name) = path.file_name() else {
+            return Err(error!("invalid output path: {}", path.display()));
+        };
+        let tempfile_name = format!(".{}.{}.tmp", file_name.to_string_lossy(), process::id());
+        let tempfile = path.with_file_name(tempfile_name);
+
+        // Write and sync the temporary file, then move it into place
+        let result = Self::write_sync(&tempfile, data).and_then(|_| fs::rename(&tempfile, path));
+        if result.is_err() {
+            // Remove the stale temporary file; this is best-effort since we are failing anyway
+            let _ = fs::remove_file(&tempfile);
+        }
+        result.map_err(|e| error!(with: e, r#"Failed to write "{}""#, path.display()))?;
+        Ok(true)
+    }
+
+    /// Writes the data to the given file and syncs it to disk
+    fn write_sync(path: &Path, data: &[u8]) -> Result<(), io::Error> {
+        let mut file = File::create(path)?;
+        file.write_all(data)?;
+        file.sync_all()
+    }
+}
+impl From<String> for Output {
+    fn from(path: String) -> Self {
+        match path.as_str() {
+            "-" => Self::Stdout,
+            _ => Self::File(PathBuf::from(path)),
+        }
+    }
+}
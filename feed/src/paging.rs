@@ -0,0 +1,77 @@
+//! Paged feeds as specified in RFC 5005
+
+use crate::output::Output;
+use crate::rss::{AtomLink, Document};
+use crate::url::UrlMapper;
+use feedme_shared::{error, Entry, Error};
+use std::path::{Path, PathBuf};
+
+/// Splits the feed into pages of `page_size` entries and returns the page documents together with their output targets
+///
+/// The main feed at `output` contains the latest entries and links to the older pages `<name>-page<n>.<ext>` next to
+/// it.
+pub fn paginate<'a>(
+    output: &Output,
+    entries: &'a [Entry],
+    page_size: usize,
+    urls: &UrlMapper,
+) -> Result<Vec<(Output, Document<'a>)>, Error> {
+    // Validate the arguments
+    let Output::File(feed_path) = output else {
+        return Err(error!("cannot write a paged feed to stdout"));
+    };
+    if page_size == 0 {
+        return Err(error!("page size must not be zero"));
+    }
+
+    // Compute the page paths and URLs
+    let page_count = entries.len().div_ceil(page_size).max(1);
+    let page_paths: Vec<_> = (1..=page_count).map(|page| page_path(feed_path, page)).collect();
+    let mut page_urls = Vec::new();
+    for page_path in &page_paths {
+        let page_url = urls.output_url(page_path)?;
+        page_urls.push(page_url);
+    }
+
+    // Create the pages
+    let mut pages = Vec::new();
+    for (index, page_path) in page_paths.into_iter().enumerate() {
+        // Select the entries; the first page contains the newest entries
+        let end = entries.len().saturating_sub(index * page_size);
+        let start = end.saturating_sub(page_size);
+
+        // Create the navigation links
+        let mut links = vec![
+            AtomLink::feed("self", page_urls[index].clone()),
+            AtomLink::feed("first", page_urls[0].clone()),
+            AtomLink::feed("last", page_urls[page_count - 1].clone()),
+        ];
+        if index > 0 {
+            links.push(AtomLink::feed("previous", page_urls[index - 1].clone()));
+        }
+        if index + 1 < page_count {
+            links.push(AtomLink::feed("next", page_urls[index + 1].clone()));
+        }
+
+        // Create the page
+        let document = Document { first_episode: start as u64 + 1, links, ..Document::new(&entries[start..end]) };
+        pages.push((Output::File(page_path), document));
+    }
+    Ok(pages)
+}
+
+/// Gets the path of the given page; the first page is the main feed
+fn page_path(feed_path: &Path, page: usize) -> PathBuf {
+    // The first page is the main feed
+    if page == 1 {
+        return feed_path.to_path_buf();
+    }
+
+    // Insert the page number before the extension
+    let stem = feed_path.file_stem().unwrap_or_default().to_string_lossy();
+    let file_name = match feed_path.extension() {
+        Some(extension) => format!("{stem}-page{page}.{}", extension.to_string_lossy()),
+        None => format!("{stem}-page{page}"),
+    };
+    feed_path.with_file_name(file_name)
+}
@@ -0,0 +1,84 @@
+//! Plain M3U8 and XSPF playlists
+
+use crate::url::UrlMapper;
+use feedme_shared::{Entry, Error, Playlist};
+use std::path::Path;
+use xml::writer::XmlEvent;
+use xml::{EmitterConfig, EventWriter};
+
+/// The name of the generated M3U8 playlist
+pub const PLAYLIST_M3U8: &str = "playlist.m3u8";
+/// The name of the generated XSPF playlist
+pub const PLAYLIST_XSPF: &str = "playlist.xspf";
+
+/// Builds an extended M3U8 playlist
+pub fn build_m3u8(dir: &Path, playlist: &Playlist, entries: &[Entry], urls: &UrlMapper) -> Result<Vec<u8>, Error> {
+    // Write the header
+    let mut m3u8 = String::from("#EXTM3U\n");
+    m3u8.push_str(&format!("#PLAYLIST:{}\n", single_line(&playlist.title)));
+
+    // Write the entries
+    for entry in entries {
+        let url = urls.media_url(dir, entry)?;
+        m3u8.push_str(&format!("#EXTINF:{},{}\n", entry.duration, single_line(&entry.title)));
+        m3u8.push_str(&format!("{url}\n"));
+    }
+    Ok(m3u8.into_bytes())
+}
+
+/// Builds an XSPF playlist
+pub fn build_xspf(dir: &Path, playlist: &Playlist, entries: &[Entry], urls: &UrlMapper) -> Result<Vec<u8>, Error> {
+    // Create the writer
+    let writer_config = EmitterConfig::new().perform_indent(true);
+    let mut writer = EventWriter::new_with_config(Vec::new(), writer_config);
+
+    // Write the playlist metadata
+    let tag = XmlEvent::start_element("playlist").attr("version", "1").default_ns("http://xspf.org/ns/0/");
+    writer.write(tag)?;
+    write_text("title", &playlist.title, &mut writer)?;
+    if let Some(author) = &playlist.author {
+        write_text("creator", author, &mut writer)?;
+    }
+    if let Some(description) = &playlist.description {
+        write_text("annotation", description, &mut writer)?;
+    }
+    if let Some(url) = &playlist.url {
+        write_text("info", url, &mut writer)?;
+    }
+    if let Some(thumbnail) = &playlist.thumbnail {
+        let url = urls.absolute_url(&dir.join(thumbnail))?;
+        write_text("image", &url, &mut writer)?;
+    }
+
+    // Write the tracks
+    writer.write(XmlEvent::start_element("trackList"))?;
+    for entry in entries {
+        let url = urls.media_url(dir, entry)?;
+        writer.write(XmlEvent::start_element("track"))?;
+        write_text("location", &url, &mut writer)?;
+        write_text("title", &entry.title, &mut writer)?;
+        if let Some(description) = &entry.description {
+            write_text("annotation", description, &mut writer)?;
+        }
+        write_text("duration", &entry.duration.saturating_mul(1000).to_string(), &mut writer)?;
+        writer.write(XmlEvent::end_element())?;
+    }
+    writer.write(XmlEvent::end_element())?;
+
+    // Close the document
+    writer.write(XmlEvent::end_element())?;
+    Ok(writer.into_inner())
+}
+
+/// Writes a text element
+fn write_text(tag: &str, text: &str, writer: &mut EventWriter<Vec<u8>>) -> Result<(), Error> {
+    writer.write(XmlEvent::start_element(tag))?;
+    writer.write(XmlEvent::characters(text))?;
+    writer.write(XmlEvent::end_element())?;
+    Ok(())
+}
+
+/// Collapses all line breaks so that the text fits into a single M3U8 line
+fn single_line(text: &str) -> String {
+    text.split(['\r', '\n']).filter(|line| !line.is_empty()).collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,50 @@
+//! Retention policies for rolling feeds
+
+use feedme_shared::{Entry, Error};
+use std::cmp::Reverse;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A retention policy
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Retention {
+    /// The amount of newest entries to keep
+    pub keep: Option<usize>,
+    /// The maximum age of an entry in days
+    pub max_age: Option<u64>,
+}
+impl Retention {
+    /// The amount of seconds per day
+    const DAY: u64 = 24 * 60 * 60;
+
+    /// Whether the policy retains all entries
+    pub fn is_unlimited(&self) -> bool {
+        self.keep.is_none() && self.max_age.is_none()
+    }
+
+    /// Returns for every entry whether it has expired according to the policy
+    pub fn expired<'a, I>(&self, entries: I) -> Result<Vec<bool>, Error>
+    where
+        I: IntoIterator<Item = &'a Entry>,
+    {
+        // Mark all entries that are too old
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let entries: Vec<_> = entries.into_iter().collect();
+        let mut expired: Vec<_> = match self.max_age {
+            Some(max_age) => {
+                let cutoff = now.saturating_sub(max_age.saturating_mul(Self::DAY));
+                entries.iter().map(|entry| entry.date < cutoff).collect()
+            }
+            None => vec![false; entries.len()],
+        };
+
+        // Mark all entries beyond the newest `keep` entries
+        if let Some(keep) = self.keep {
+            let mut by_date: Vec<_> = (0..entries.len()).collect();
+            by_date.sort_by_key(|index| Reverse(entries[*index].date));
+            for index in by_date.into_iter().skip(keep) {
+                expired[index] = true;
+            }
+        }
+        Ok(expired)
+    }
+}
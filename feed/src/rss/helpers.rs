@@ -1,10 +1,15 @@
 //! Some XML helpers
 
 use feedme_shared::{Error, Uuid};
+use serde::{Deserialize, Serialize};
 use std::io::Write;
 use xml::writer::XmlEvent;
 use xml::EventWriter;
 
+/// A string that is written as CDATA section instead of escaped characters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CData(pub String);
+
 /// A trait for untagged primitives that can be written to an XML document
 pub trait XmlWritePrimitive<T>
 where
@@ -50,6 +55,25 @@ where
         string.write(tag, writer)
     }
 }
+impl<T> XmlWritePrimitive<T> for CData
+where
+    T: Write,
+{
+    fn write(&self, tag: &str, writer: &mut EventWriter<T>) -> Result<(), Error> {
+        // Serialize the tag
+        let tag_start = XmlEvent::start_element(tag);
+        writer.write(tag_start)?;
+
+        // Write the value as CDATA section
+        let value = XmlEvent::cdata(&self.0);
+        writer.write(value)?;
+
+        // Close element
+        let tag_end = XmlEvent::end_element().name(tag);
+        writer.write(tag_end)?;
+        Ok(())
+    }
+}
 impl<T, W> XmlWritePrimitive<W> for Option<T>
 where
     T: XmlWritePrimitive<W>,
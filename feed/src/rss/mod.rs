@@ -3,139 +3,140 @@
 mod helpers;
 mod schema;
 
-use crate::rss::helpers::XmlWrite;
+use crate::cache::Cache;
+use crate::notes;
+use crate::rss::helpers::{CData, XmlWrite};
+pub use crate::rss::schema::AtomLink;
 use crate::rss::schema::{Channel, Enclosure, Feed, Image, Item};
-use feedme_shared::{error, Entry, Error, Playlist};
-use std::collections::BTreeSet;
-use std::fs::{self, File};
-use std::path::{Component, Path};
+use crate::url::UrlMapper;
+use feedme_shared::{Entry, Error, Playlist};
+use std::path::Path;
+use std::str;
+use std::time::{SystemTime, UNIX_EPOCH};
 use xml::{EmitterConfig, EventWriter};
 
-/// Builds a podcast feed from existing .feedme-metadata files
-pub fn build_feed(base_url: &str, webroot: &str) -> Result<(), Error> {
-    // Load the metadata
-    let (playlist, entries) = collect_metadata()?;
+/// A feed document, i.e. an entire feed or a single page of a paged feed
+#[derive(Debug, Clone)]
+pub struct Document<'a> {
+    /// The entries within the document
+    pub entries: &'a [Entry],
+    /// The episode number of the first entry
+    pub first_episode: u64,
+    /// The Atom links that are added to the channel as `atom:link` elements
+    pub links: Vec<AtomLink>,
+    /// The previous version of the document whose `lastBuildDate` is kept if the content did not change
+    pub previous: Option<Vec<u8>>,
+    /// The `lastBuildDate` as UNIX timestamp if the content has changed
+    pub build_date: u64,
+}
+impl<'a> Document<'a> {
+    /// Creates a new document for the given entries, starting with episode `1` and built now
+    pub fn new(entries: &'a [Entry]) -> Self {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_secs()).unwrap_or_default();
+        Self { entries, first_episode: 1, links: Vec::new(), previous: None, build_date: now }
+    }
+}
 
+/// Builds a podcast feed document from the metadata of the playlist in `dir` and returns the serialized feed
+///
+/// The output only depends on the inputs, so the same inputs always produce the same bytes. If a `cache` is given,
+/// unchanged items are taken from the cache instead of being rendered again.
+pub fn build_feed(
+    dir: &Path,
+    playlist: &Playlist,
+    document: &Document,
+    mut cache: Option<&mut Cache>,
+    urls: &UrlMapper,
+) -> Result<Vec<u8>, Error> {
     // Generate the thumbnail item
     let mut thumbnail = None;
-    if let Some(thumbnail_) = playlist.thumbnail {
-        let url = absolute_url(&thumbnail_, webroot, base_url)?;
+    if let Some(thumbnail_) = &playlist.thumbnail {
+        let url = urls.absolute_url(&dir.join(thumbnail_))?;
         thumbnail = Some(Image { url });
     }
 
     // Serialize playlist
     let mut channel = Channel {
-        title: playlist.title,
+        title: playlist.title.clone(),
         itunes_type: "Serial".to_string(),
-        link: playlist.url,
-        itunes_author: playlist.author,
-        description: playlist.description,
+        link: playlist.url.clone(),
+        itunes_author: playlist.author.clone(),
+        description: playlist.description.clone(),
+        pub_date: document.entries.iter().map(|entry| entry.date).max(),
+        last_build_date: document.build_date,
         itunes_image: thumbnail,
+        atom_links: document.links.clone(),
         items: Vec::new(),
     };
 
     // Serialize items
-    for (index, entry) in entries.into_iter().enumerate() {
-        // Build the enclosure entry referencing the file
-        let enclosure = {
-            let url = absolute_url(&entry.file, webroot, base_url)?;
-            Enclosure { length: entry.size, type_: entry.type_, url }
-        };
-
-        // Create the playlist item
-        let item = Item {
-            title: entry.title,
-            itunes_episode: (index as u64) + 1,
-            description: entry.description,
-            enclosure,
-            guid: entry.uuid,
-            pub_date: entry.date,
-            itunes_duration: entry.duration,
+    for (episode, entry) in (document.first_episode..).zip(document.entries) {
+        // Build the item or take it from the cache
+        let item = match cache.as_deref_mut() {
+            None => build_item(dir, entry, episode, urls)?,
+            Some(cache) => {
+                let fingerprint = Cache::fingerprint(dir, entry, &(episode, urls))?;
+                cache.get_or_build(fingerprint, || build_item(dir, entry, episode, urls))?
+            }
         };
         channel.items.push(item);
     }
 
-    // Create the writer
-    let file = File::create("feed.rss")?;
-    let writer_config = EmitterConfig::new().perform_indent(true);
-    let mut writer = EventWriter::new_with_config(file, writer_config);
-
-    // Write the feed
-    let feed = Feed { channel };
-    feed.write(&mut writer)?;
-    Ok(())
-}
-
-/// Collect all metadata files
-fn collect_metadata() -> Result<(Playlist, Vec<Entry>), Error> {
-    // Read the playlist
-    let playlist_bin =
-        fs::read("playlist-meta.feedme").map_err(|e| error!(with: e, r#"Failed to open "playlist-meta.feedme""#))?;
-    let playlist = serde_json::from_slice(&playlist_bin)?;
-
-    // List all entry files and sort them
-    let mut entry_names = BTreeSet::new();
-    'list_dir: for file in fs::read_dir(".")? {
-        // Unwrap the entry or skip it
-        let Ok(file) = file else {
-            continue 'list_dir;
-        };
-
-        // Get the filename and check that it references a feedme file
-        let file_name_os = file.file_name();
-        let Some(file_name) = file_name_os.to_str() else {
-            continue 'list_dir;
-        };
-
-        // Ensure that the file is a playlist entry
-        if !file_name.starts_with("playlist-entry") {
-            continue 'list_dir;
-        }
-        if !file_name.ends_with(".feedme") {
-            continue 'list_dir;
+    // Keep the previous build date if the content did not change
+    let mut feed = Feed { channel };
+    if let Some(previous) = &document.previous {
+        if let Some(last_build_date) = find_last_build_date(previous) {
+            feed.channel.last_build_date = last_build_date;
+            let serialized = serialize(&feed)?;
+            if serialized == *previous {
+                return Ok(serialized);
+            }
         }
-
-        // Collect the entry
-        entry_names.insert(file_name.to_string());
     }
 
-    // Process the entries in order
-    let mut entries = Vec::new();
-    for entry_name in entry_names {
-        // Parse the entry
-        let entry_bin = fs::read(&entry_name).map_err(|e| error!(with: e, r#"Failed to open "{entry_name}""#))?;
-        let entry = serde_json::from_slice(&entry_bin)?;
-        entries.push(entry);
-    }
-    Ok((playlist, entries))
+    // Write the feed with the new build date
+    feed.channel.last_build_date = document.build_date;
+    serialize(&feed)
 }
 
-/// Creates an absolute URL for a file path
-fn absolute_url(file: &str, webroot: &str, base_url: &str) -> Result<String, Error> {
-    // Create the relative path
-    let canonical = Path::new(file).canonicalize().map_err(|e| error!(with: e, r#"Failed canonicalize "{file}""#))?;
-    if !canonical.starts_with(webroot) {
-        return Err(error!("file is not within webroot: {}", canonical.display()));
-    }
-
-    // Create the relative path and the URL
-    let relative_path = canonical.strip_prefix(webroot)?;
-    let mut url_components = vec![base_url.to_string()];
-
-    // Escape the individual path components
-    for component in relative_path.components() {
-        // Get the path component
-        let Component::Normal(component) = component else {
-            return Err(error!("unexpected path component: {component:?}"));
-        };
+/// Serializes the feed
+fn serialize(feed: &Feed) -> Result<Vec<u8>, Error> {
+    let writer_config = EmitterConfig::new().perform_indent(true);
+    let mut writer = EventWriter::new_with_config(Vec::new(), writer_config);
+    feed.write(&mut writer)?;
+    Ok(writer.into_inner())
+}
 
-        // Escape the path component
-        let component_str = component.to_str().ok_or(error!("path is not valid UTF-8"))?;
-        let component = urlencoding::encode(component_str);
-        url_components.push(component.to_string());
-    }
+/// Extracts the `lastBuildDate` from a serialized feed
+fn find_last_build_date(feed: &[u8]) -> Option<u64> {
+    let feed = str::from_utf8(feed).ok()?;
+    let (_, date) = feed.split_once("<lastBuildDate>")?;
+    let (date, _) = date.split_once("</lastBuildDate>")?;
+    schema::parse_date(date)
+}
 
-    // Join the URL components
-    let url = url_components.join("/");
-    Ok(url)
+/// Builds a single feed item
+fn build_item(dir: &Path, entry: &Entry, episode: u64, urls: &UrlMapper) -> Result<Item, Error> {
+    // Build the enclosure entry referencing the file
+    let media_url = urls.media_url(dir, entry)?;
+    let enclosure = Enclosure { length: entry.size, type_: entry.type_.clone(), url: media_url.clone() };
+
+    // Render the plain text description and the HTML show notes including the chapters
+    let description = entry.description.as_deref().map(|description| notes::render_text(description, entry.markup));
+    let content_encoded = notes::render_html(entry, &media_url).map(CData);
+
+    // Create the playlist item
+    let item = Item {
+        title: entry.title.clone(),
+        itunes_episode: episode,
+        itunes_author: entry.author.clone(),
+        itunes_summary: description.clone(),
+        description,
+        content_encoded,
+        enclosure,
+        guid: entry.uuid,
+        pub_date: entry.date,
+        itunes_duration: entry.duration,
+    };
+    Ok(item)
 }
@@ -1,7 +1,8 @@
 //! Common RSS podcast feed XML tags
 
-use crate::rss::helpers::{XmlWrite, XmlWritePrimitive};
+use crate::rss::helpers::{CData, XmlWrite, XmlWritePrimitive};
 use feedme_shared::{error, Error, Uuid};
+use serde::{Deserialize, Serialize};
 use std::io::Write;
 use time::format_description::well_known::Rfc2822;
 use time::OffsetDateTime;
@@ -9,7 +10,7 @@ use xml::writer::XmlEvent;
 use xml::EventWriter;
 
 /// An enclosure object
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Enclosure {
     /// The file size in bytes (`length`)
     pub length: u64,
@@ -60,15 +61,62 @@ where
     }
 }
 
-/// A playlist item
+/// An Atom link (e.g. an RFC 5005 paging link)
 #[derive(Debug, Clone)]
+pub struct AtomLink {
+    /// The link relation (`rel`)
+    pub rel: String,
+    /// The MIME type of the link target (`type`)
+    pub type_: Option<String>,
+    /// The link target (`href`)
+    pub href: String,
+}
+impl AtomLink {
+    /// Creates a link to a feed document with the given relation
+    pub fn feed(rel: &str, href: String) -> Self {
+        Self { rel: rel.to_string(), type_: Some("application/rss+xml".to_string()), href }
+    }
+
+    /// Creates a link to a WebSub hub
+    pub fn hub(href: String) -> Self {
+        Self { rel: "hub".to_string(), type_: None, href }
+    }
+}
+impl<T> XmlWrite<T> for AtomLink
+where
+    T: Write,
+{
+    fn write(&self, writer: &mut EventWriter<T>) -> Result<(), Error> {
+        // Serialize the tag
+        let mut tag_start = XmlEvent::start_element("atom:link").attr("rel", &self.rel);
+        if let Some(type_) = &self.type_ {
+            tag_start = tag_start.attr("type", type_);
+        }
+        let tag_start = tag_start.attr("href", &self.href);
+        writer.write(tag_start)?;
+
+        // Close element
+        let tag_end = XmlEvent::end_element().name("atom:link");
+        writer.write(tag_end)?;
+        Ok(())
+    }
+}
+
+/// A playlist item
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Item {
     /// An item's title (`title`)
     pub title: String,
     /// The episode number (`itunes:episode`)
     pub itunes_episode: u64,
-    /// The item's description (`description`)
+    /// The item's author (`itunes:author`)
+    pub itunes_author: Option<String>,
+    /// The item's plain text description (`description`)
     pub description: Option<String>,
+    /// The item's plain text summary (`itunes:summary`)
+    pub itunes_summary: Option<String>,
+    /// The item's HTML show notes (`content:encoded`)
+    pub content_encoded: Option<CData>,
     /// An item's enclosure tag (`enclosure`)
     pub enclosure: Enclosure,
     /// An item's globally unique ID ('guid')
@@ -83,27 +131,22 @@ where
     T: Write,
 {
     fn write(&self, writer: &mut EventWriter<T>) -> Result<(), Error> {
-        // Format the date
-        let pub_date = {
-            let timestamp = i64::try_from(self.pub_date).map_err(|e| error!(with: e, "timestamp is too large"))?;
-            let date = OffsetDateTime::from_unix_timestamp(timestamp)?;
-            date.format(&Rfc2822)?
-        };
-
         // Write object
         writer.write(XmlEvent::start_element("item"))?;
         self.title.write("title", writer)?;
         self.itunes_episode.write("itunes:episode", writer)?;
+        self.itunes_author.write("itunes:author", writer)?;
         self.description.write("description", writer)?;
+        self.itunes_summary.write("itunes:summary", writer)?;
+        self.content_encoded.write("content:encoded", writer)?;
         self.enclosure.write(writer)?;
         self.guid.write("guid", writer)?;
-        pub_date.write("pubDate", writer)?;
+        format_date(self.pub_date)?.write("pubDate", writer)?;
         self.itunes_duration.write("itunes:duration", writer)?;
         writer.write(XmlEvent::end_element())?;
         Ok(())
     }
 }
-
 /// A channel object
 #[derive(Debug, Clone)]
 pub struct Channel {
@@ -117,8 +160,14 @@ pub struct Channel {
     pub itunes_author: Option<String>,
     /// The playlist description (`description`)
     pub description: Option<String>,
+    /// The publication date of the newest item (`pubDate`)
+    pub pub_date: Option<u64>,
+    /// The date when the feed content has last changed (`lastBuildDate`)
+    pub last_build_date: u64,
     /// The link to the playlist thumbnail (`itunes:image`)
     pub itunes_image: Option<Image>,
+    /// The Atom links of this feed document (`atom:link`)
+    pub atom_links: Vec<AtomLink>,
     /// The playlist member items
     pub items: Vec<Item>,
 }
@@ -134,7 +183,12 @@ where
         self.link.write("link", writer)?;
         self.itunes_author.write("itunes:author", writer)?;
         self.description.write("description", writer)?;
+        self.pub_date.map(format_date).transpose()?.write("pubDate", writer)?;
+        format_date(self.last_build_date)?.write("lastBuildDate", writer)?;
         self.itunes_image.write(writer)?;
+        for atom_link in &self.atom_links {
+            atom_link.write(writer)?;
+        }
 
         // Write items
         for item in &self.items {
@@ -160,7 +214,8 @@ where
         let tag = XmlEvent::start_element("rss")
             .attr("version", "2.0")
             .ns("itunes", "http://www.itunes.com/dtds/podcast-1.0.dtd")
-            .ns("content", "http://purl.org/rss/1.0/modules/content/");
+            .ns("content", "http://purl.org/rss/1.0/modules/content/")
+            .ns("atom", "http://www.w3.org/2005/Atom");
         writer.write(tag)?;
 
         // Write the channel
@@ -169,3 +224,16 @@ where
         Ok(())
     }
 }
+
+/// Formats a UNIX timestamp as RFC 2822 date
+fn format_date(timestamp: u64) -> Result<String, Error> {
+    let timestamp = i64::try_from(timestamp).map_err(|e| error!(with: e, "timestamp is too large"))?;
+    let date = OffsetDateTime::from_unix_timestamp(timestamp)?;
+    Ok(date.format(&Rfc2822)?)
+}
+
+/// Parses an RFC 2822 date into a UNIX timestamp
+pub fn parse_date(date: &str) -> Option<u64> {
+    let date = OffsetDateTime::parse(date, &Rfc2822).ok()?;
+    u64::try_from(date.unix_timestamp()).ok()
+}
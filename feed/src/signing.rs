@@ -0,0 +1,211 @@
+//! HMAC-signed expiring URLs
+//!
+//! A signed URL carries an `expires` UNIX timestamp and a hex-encoded HMAC-SHA256 `signature` over its request target,
+//! i.e. the path and query without the trailing `signature` parameter. Since scheme and host are not signed, the URL
+//! can be verified by a reverse proxy that only sees the request target.
+
+use feedme_shared::{error, Error};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::env;
+use std::hash::{Hash, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The HMAC instance used for signing
+type HmacSha256 = Hmac<Sha256>;
+
+/// Signs URLs with an expiry date
+#[derive(Debug, Clone)]
+pub struct Signer {
+    /// The signing key
+    key: Vec<u8>,
+    /// An identifier that is derived from the signing key without revealing it
+    key_id: Vec<u8>,
+    /// The expiry date of all signatures as UNIX timestamp
+    expires: u64,
+}
+impl Signer {
+    /// Creates a new signer whose signatures are valid for at least `ttl` and at most twice `ttl` seconds
+    ///
+    /// The expiry date is rounded up to a multiple of `ttl`, so that all builds within the same `ttl` window produce the
+    /// same URLs.
+    pub fn new(key: Vec<u8>, ttl: u64) -> Result<Self, Error> {
+        // Validate the arguments
+        if key.is_empty() {
+            return Err(error!("signing key must not be empty"));
+        }
+        if ttl == 0 {
+            return Err(error!("signature lifetime must not be zero"));
+        }
+
+        // Derive the key ID
+        let mut mac = HmacSha256::new_from_slice(&key).expect("HMAC accepts keys of any length");
+        mac.update(b"feedme key id");
+        let key_id = mac.finalize().into_bytes().to_vec();
+
+        // Compute the expiry date
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let expires = (now / ttl).checked_add(2).and_then(|windows| windows.checked_mul(ttl));
+        let expires = expires.ok_or(error!("signature lifetime is too large"))?;
+        Ok(Self { key, key_id, expires })
+    }
+
+    /// Creates a new signer with the key from the `FEEDME_SIGNING_KEY` environment variable
+    pub fn from_env(ttl: u64) -> Result<Self, Error> {
+        let key = load_key()?;
+        Self::new(key, ttl)
+    }
+
+    /// Signs the URL
+    pub fn sign(&self, url: &str) -> String {
+        // Append the expiry date
+        let separator = if url.contains('?') { '&' } else { '?' };
+        let url = format!("{url}{separator}expires={}", self.expires);
+
+        // Sign the request target
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(request_target(&url).as_bytes());
+        let signature: String = mac.finalize().into_bytes().iter().map(|byte| format!("{byte:02x}")).collect();
+        format!("{url}&signature={signature}")
+    }
+}
+impl Hash for Signer {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key_id.hash(state);
+        self.expires.hash(state);
+    }
+}
+
+/// Loads the signing key from the `FEEDME_SIGNING_KEY` environment variable
+pub fn load_key() -> Result<Vec<u8>, Error> {
+    let Ok(key) = env::var("FEEDME_SIGNING_KEY") else {
+        return Err(error!("missing FEEDME_SIGNING_KEY environment variable"));
+    };
+    Ok(key.into_bytes())
+}
+
+/// Verifies a signed URL or request target against the key and checks that it has not expired at `now`
+pub fn verify(key: &[u8], url: &str, now: u64) -> bool {
+    // Split the signature from the signed part
+    let target = request_target(url);
+    let Some((signed, signature)) = target.rsplit_once("&signature=") else {
+        return false;
+    };
+
+    // Check the expiry date
+    let query = signed.split_once('?').map(|(_, query)| query).unwrap_or_default();
+    let expires = query.split('&').find_map(|param| param.strip_prefix("expires="));
+    let Some(expires) = expires.and_then(|expires| expires.parse::<u64>().ok()) else {
+        return false;
+    };
+    if expires < now {
+        return false;
+    }
+
+    // Decode the signature
+    let signature: Option<Vec<u8>> = match signature.len() % 2 {
+        0 => (0..signature.len()).step_by(2).map(|i| u8::from_str_radix(signature.get(i..i + 2)?, 16).ok()).collect(),
+        _ => None,
+    };
+    let Some(signature) = signature else {
+        return false;
+    };
+
+    // Verify the signature in constant time
+    let Ok(mut mac) = HmacSha256::new_from_slice(key) else {
+        return false;
+    };
+    mac.update(signed.as_bytes());
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Strips the scheme and authority from a URL
+fn request_target(url: &str) -> &str {
+    // Check if we have an absolute URL
+    let Some((_scheme, rest)) = url.split_once("://") else {
+        return url;
+    };
+
+    // Strip the authority
+    match rest.find('/') {
+        Some(index) => &rest[index..],
+        None => "/",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The signing key for the tests
+    const KEY: &[u8] = b"correct horse battery staple";
+
+    /// Signs a media URL and returns the signer and the signed URL
+    fn fixture() -> (Signer, String) {
+        let signer = Signer::new(KEY.to_vec(), 3600).expect("failed to create signer");
+        let url = signer.sign("https://example.org/my%20podcast/episode.mp4?token=abc");
+        (signer, url)
+    }
+
+    #[test]
+    fn round_trip() {
+        let (signer, url) = fixture();
+        assert!(url.starts_with("https://example.org/my%20podcast/episode.mp4?token=abc&expires="));
+        assert!(verify(KEY, &url, signer.expires));
+
+        // Reverse proxies only see the request target
+        let target = url.strip_prefix("https://example.org").expect("unexpected URL");
+        assert!(verify(KEY, target, signer.expires));
+    }
+
+    #[test]
+    fn expired() {
+        let (signer, url) = fixture();
+        assert!(!verify(KEY, &url, signer.expires + 1));
+
+        // Extending the expiry date invalidates the signature
+        let extended = url.replace(&format!("expires={}", signer.expires), &format!("expires={}", signer.expires + 1));
+        assert_ne!(extended, url);
+        assert!(!verify(KEY, &extended, signer.expires + 1));
+    }
+
+    #[test]
+    fn tampered_path() {
+        let (signer, url) = fixture();
+        let tampered = url.replace("episode.mp4", "episode2.mp4");
+        assert!(!verify(KEY, &tampered, signer.expires));
+        let tampered = url.replace("token=abc", "token=abd");
+        assert!(!verify(KEY, &tampered, signer.expires));
+    }
+
+    #[test]
+    fn tampered_signature() {
+        let (signer, url) = fixture();
+        let (signed, signature) = url.rsplit_once("&signature=").expect("URL is not signed");
+
+        // Flip the last digit of the signature
+        let (head, last) = signature.split_at(signature.len() - 1);
+        let flipped = if last == "0" { "1" } else { "0" };
+        assert!(!verify(KEY, &format!("{signed}&signature={head}{flipped}"), signer.expires));
+
+        // Truncated, malformed or missing signatures are rejected too
+        assert!(!verify(KEY, &format!("{signed}&signature={head}"), signer.expires));
+        assert!(!verify(KEY, &format!("{signed}&signature=zz"), signer.expires));
+        assert!(!verify(KEY, signed, signer.expires));
+    }
+
+    #[test]
+    fn different_key() {
+        let (signer, url) = fixture();
+        assert!(!verify(b"another key", &url, signer.expires));
+    }
+
+    #[test]
+    fn large_ttl() {
+        let error = Signer::new(KEY.to_vec(), u64::MAX).expect_err("overflowing lifetime was accepted");
+        assert!(error.to_string().contains("signature lifetime is too large"));
+
+        // The largest lifetimes that still fit are accepted
+        assert!(Signer::new(KEY.to_vec(), u64::MAX / 4).is_ok());
+    }
+}
@@ -0,0 +1,261 @@
+//! Private per-subscriber feeds
+
+use feedme_shared::{error, Error};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The minimum length of an access token
+const TOKEN_LEN_MIN: usize = 16;
+
+/// The subscribers of a private feed
+///
+/// Every active subscriber gets their own copy of the feed at `<stem>-<token>.<ext>`, and every enclosure URL within
+/// that copy carries the subscriber's token, so that leaked feeds or URLs can be traced back to the subscriber.
+#[derive(Debug, Clone, Default)]
+pub struct Subscribers {
+    /// The access tokens by subscriber name
+    tokens: BTreeMap<String, String>,
+    /// The names of the revoked subscribers
+    revoked: BTreeSet<String>,
+}
+impl Subscribers {
+    /// Loads the subscriber list and the optional revocation list
+    ///
+    /// The subscriber list is a JSON object that maps subscriber names to their access tokens; the revocation list is a
+    /// JSON array of subscriber names.
+    pub fn load(subscribers: &Path, revoked: Option<&Path>) -> Result<Self, Error> {
+        // Load the subscribers
+        let tokens_json =
+            fs::read(subscribers).map_err(|e| error!(with: e, r#"Failed to open "{}""#, subscribers.display()))?;
+        let tokens: BTreeMap<String, String> = serde_json::from_slice(&tokens_json)?;
+
+        // Validate the tokens
+        let mut unique_tokens = BTreeSet::new();
+        for (name, token) in &tokens {
+            // Ensure that the token is long enough and can be used within file names and URLs
+            let is_alphanumeric = token.chars().all(|char| char.is_ascii_alphanumeric());
+            if token.len() < TOKEN_LEN_MIN || !is_alphanumeric {
+                return Err(error!(
+                    "token of subscriber {name} is not at least {TOKEN_LEN_MIN} alphanumeric characters"
+                ));
+            }
+            if !unique_tokens.insert(token) {
+                return Err(error!("token of subscriber {name} is not unique"));
+            }
+        }
+
+        // Load the revocation list
+        let mut revoked_names = BTreeSet::new();
+        if let Some(revoked) = revoked {
+            let revoked_json =
+                fs::read(revoked).map_err(|e| error!(with: e, r#"Failed to open "{}""#, revoked.display()))?;
+            revoked_names = serde_json::from_slice(&revoked_json)?;
+        }
+        for name in &revoked_names {
+            if !tokens.contains_key(name) {
+                eprintln!("[feedme-feed] Ignoring revoked subscriber that is not in the subscriber list: {name}");
+            }
+        }
+        Ok(Self { tokens, revoked: revoked_names })
+    }
+
+    /// The names and tokens of all subscribers that have not been revoked
+    pub fn active(&self) -> impl Iterator<Item = (&str, &str)> {
+        let active = self.tokens.iter().filter(|(name, _)| !self.revoked.contains(*name));
+        active.map(|(name, token)| (name.as_str(), token.as_str()))
+    }
+
+    /// Removes all subscriber feeds, feed pages and compressed copies next to `feed_path` whose token is not active
+    ///
+    /// This covers revoked subscribers as well as subscribers that have been removed from the list or whose token has
+    /// been rotated.
+    pub fn remove_inactive(&self, feed_path: &Path) -> Result<(), Error> {
+        // Get the file name prefix of the subscriber feeds and the active tokens
+        let stem = feed_path.file_stem().unwrap_or_default().to_string_lossy();
+        let prefix = format!("{stem}-");
+        let active: BTreeSet<_> = self.active().map(|(_, token)| token).collect();
+
+        // Remove the feeds with inactive tokens
+        'read_dir: for file in fs::read_dir(feed_dir(feed_path))? {
+            // Unwrap the entry or skip it
+            let Ok(file) = file else {
+                continue 'read_dir;
+            };
+
+            // Get the token from `<stem>-<token>.<ext>[.<suffix>]` or `<stem>-<token>-page<n>.<ext>[.<suffix>]`
+            let file_name = file.file_name().to_string_lossy().to_string();
+            let Some(rest) = file_name.strip_prefix(&prefix) else {
+                continue 'read_dir;
+            };
+            let token_len = rest.find(|char: char| !char.is_ascii_alphanumeric()).unwrap_or(rest.len());
+            let (token, rest) = rest.split_at(token_len);
+            let is_feed = rest.starts_with('.') || rest.starts_with("-page");
+            if token.len() < TOKEN_LEN_MIN || !is_feed || !file.file_type()?.is_file() {
+                continue 'read_dir;
+            }
+
+            // Remove the file if the token is not active
+            if !active.contains(token) {
+                eprintln!("[feedme-feed] Removing subscriber feed with inactive token: {file_name}");
+                fs::remove_file(file.path())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the public feed at `feed_path` together with its pages and compressed copies
+    ///
+    /// A public feed that is left over from before the playlist switched to subscriber feeds would still be published
+    /// without any token, so leaks could not be traced back to a subscriber anymore.
+    pub fn remove_public(&self, feed_path: &Path) -> Result<(), Error> {
+        // Get the file name of the feed and the parts of the page names
+        let feed_name = feed_path.file_name().unwrap_or_default().to_string_lossy();
+        let stem = feed_path.file_stem().unwrap_or_default().to_string_lossy();
+        let page_prefix = format!("{stem}-page");
+        let extension = feed_path.extension().map(|extension| format!(".{}", extension.to_string_lossy()));
+        let tokens: BTreeSet<_> = self.tokens.values().map(String::as_str).collect();
+
+        // Remove the public feed files
+        'read_dir: for file in fs::read_dir(feed_dir(feed_path))? {
+            // Unwrap the entry or skip it
+            let Ok(file) = file else {
+                continue 'read_dir;
+            };
+
+            // Match `<stem>.<ext>[.<suffix>]` and `<stem>-page<n>.<ext>[.<suffix>]`, but not the feeds of subscribers
+            // whose token happens to look like a page number
+            let file_name = file.file_name().to_string_lossy().to_string();
+            let name = file_name.strip_suffix(".gz").or(file_name.strip_suffix(".br")).unwrap_or(&file_name);
+            let page = name.strip_prefix(&page_prefix).and_then(|rest| match &extension {
+                Some(extension) => rest.strip_suffix(extension.as_str()),
+                None => Some(rest),
+            });
+            let is_page = page.is_some_and(|page| {
+                let is_number = !page.is_empty() && page.chars().all(|char| char.is_ascii_digit());
+                is_number && !tokens.contains(format!("page{page}").as_str())
+            });
+            if (name != feed_name && !is_page) || !file.file_type()?.is_file() {
+                continue 'read_dir;
+            }
+
+            // Remove the file
+            eprintln!("[feedme-feed] Removing public feed: {file_name}");
+            fs::remove_file(file.path())?;
+        }
+        Ok(())
+    }
+
+    /// Gets the path of a subscriber's copy of the feed at `feed_path`
+    pub fn feed_path(feed_path: &Path, token: &str) -> PathBuf {
+        let stem = feed_path.file_stem().unwrap_or_default().to_string_lossy();
+        let file_name = match feed_path.extension() {
+            Some(extension) => format!("{stem}-{token}.{}", extension.to_string_lossy()),
+            None => format!("{stem}-{token}"),
+        };
+        feed_path.with_file_name(file_name)
+    }
+}
+
+/// Gets the directory of the feed at `feed_path`
+fn feed_dir(feed_path: &Path) -> &Path {
+    match feed_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    /// Files next to the subscriber feeds that must never be removed
+    const UNRELATED: [&str; 5] =
+        ["feed.rss", "feed.rss.gz", "feed-page2.rss", "feed-notes.txt", "other-0123456789abcdef.rss"];
+
+    /// The files of a subscriber feed with the given token
+    fn feed_files(token: &str) -> Vec<String> {
+        let files = ["feed-{}.rss", "feed-{}.rss.gz", "feed-{}-page2.rss", "feed-{}-page2.rss.br"];
+        files.iter().map(|file| file.replace("{}", token)).collect()
+    }
+
+    /// Creates the given files and the unrelated public feed files within a temporary directory
+    fn fixture(test: &str, files: &[String]) -> TempDir {
+        let dir = TempDir::new(test);
+        for file in files.iter().map(String::as_str).chain(UNRELATED) {
+            fs::write(dir.path().join(file), b"").expect("failed to create file");
+        }
+        dir
+    }
+
+    /// Lists the files within the directory
+    fn list(dir: &Path) -> BTreeSet<String> {
+        let files = fs::read_dir(dir).expect("failed to list directory");
+        files.map(|file| file.expect("failed to list file").file_name().to_string_lossy().to_string()).collect()
+    }
+
+    /// The expected directory listing: the given subscriber files and the unrelated files
+    fn expected(files: &[String]) -> BTreeSet<String> {
+        files.iter().cloned().chain(UNRELATED.map(str::to_string)).collect()
+    }
+
+    #[test]
+    fn removed_token() {
+        // Bob has been removed from the subscriber list
+        let (alice, bob) = ("aaaaaaaaaaaaaaaa0", "bbbbbbbbbbbbbbbb0");
+        let dir = fixture("subscribers-removed_token", &[feed_files(alice), feed_files(bob)].concat());
+        let subscribers = Subscribers {
+            tokens: BTreeMap::from([("alice".to_string(), alice.to_string())]),
+            revoked: BTreeSet::new(),
+        };
+
+        // Only Alice's feeds must remain
+        subscribers.remove_inactive(&dir.path().join("feed.rss")).expect("failed to remove feeds");
+        assert_eq!(list(dir.path()), expected(&feed_files(alice)));
+    }
+
+    #[test]
+    fn rotated_token() {
+        // Alice got a new token
+        let (old, new) = ("aaaaaaaaaaaaaaaa0", "aaaaaaaaaaaaaaaa1");
+        let dir = fixture("subscribers-rotated_token", &[feed_files(old), feed_files(new)].concat());
+        let subscribers =
+            Subscribers { tokens: BTreeMap::from([("alice".to_string(), new.to_string())]), revoked: BTreeSet::new() };
+
+        // Only the feeds with the new token must remain
+        subscribers.remove_inactive(&dir.path().join("feed.rss")).expect("failed to remove feeds");
+        assert_eq!(list(dir.path()), expected(&feed_files(new)));
+    }
+
+    #[test]
+    fn revoked_token() {
+        // Bob has been revoked
+        let (alice, bob) = ("aaaaaaaaaaaaaaaa0", "bbbbbbbbbbbbbbbb0");
+        let dir = fixture("subscribers-revoked_token", &[feed_files(alice), feed_files(bob)].concat());
+        let tokens = BTreeMap::from([("alice".to_string(), alice.to_string()), ("bob".to_string(), bob.to_string())]);
+        let subscribers = Subscribers { tokens, revoked: BTreeSet::from(["bob".to_string()]) };
+
+        // Only Alice's feeds must remain
+        subscribers.remove_inactive(&dir.path().join("feed.rss")).expect("failed to remove feeds");
+        assert_eq!(list(dir.path()), expected(&feed_files(alice)));
+    }
+
+    #[test]
+    fn public_feed() {
+        // The playlist has switched to subscriber feeds
+        let alice = "aaaaaaaaaaaaaaaa0";
+        let public = ["feed-page3.rss.br", "feed-page10.rss"].map(str::to_string);
+        let dir = fixture("subscribers-public_feed", &[feed_files(alice), public.to_vec()].concat());
+        let subscribers = Subscribers {
+            tokens: BTreeMap::from([("alice".to_string(), alice.to_string())]),
+            revoked: BTreeSet::new(),
+        };
+
+        // The public feed, its pages and their compressed copies must be gone
+        subscribers.remove_public(&dir.path().join("feed.rss")).expect("failed to remove public feed");
+        let remaining = ["feed-notes.txt", "other-0123456789abcdef.rss"].map(str::to_string);
+        let expected: BTreeSet<_> = feed_files(alice).into_iter().chain(remaining).collect();
+        assert_eq!(list(dir.path()), expected);
+    }
+}
@@ -0,0 +1,33 @@
+//! Shared helpers for the unit tests
+
+use std::path::{Path, PathBuf};
+use std::{env, fs, process};
+
+/// A temporary directory that is removed again when it is dropped
+#[derive(Debug)]
+pub struct TempDir {
+    /// The canonical path of the directory
+    path: PathBuf,
+}
+impl TempDir {
+    /// Creates a new, empty temporary directory whose name contains the name of the test
+    pub fn new(test: &str) -> Self {
+        // Create a fresh directory; leftovers from an aborted run are removed first
+        let path = env::temp_dir().join(format!("feedme-test-{test}-{}", process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).expect("failed to create temporary directory");
+        let path = path.canonicalize().expect("invalid temporary directory");
+        Self { path }
+    }
+
+    /// The path of the directory
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        // Cleaning up is best-effort; a failure must not mask the test result
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,352 @@
+//! Maps filesystem paths to URLs
+
+use crate::signing::Signer;
+use feedme_shared::{error, Entry, Error};
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf, Prefix};
+use std::{env, fs};
+
+/// The name of the optional per-playlist URL mapping file
+///
+/// The file contains a JSON object that maps webroot directories to their base URLs.
+pub const URL_MAPPINGS: &str = "url-mappings.feedme";
+
+/// A webroot directory and the URL that corresponds to it
+#[derive(Debug, Clone, Hash)]
+struct Mapping {
+    /// The webroot directory
+    webroot: PathBuf,
+    /// The server URL that corresponds to the webroot
+    base_url: String,
+}
+
+/// Maps files within one or more webroots to absolute URLs
+///
+/// If a file is within multiple webroots, the longest matching webroot is used.
+#[derive(Debug, Clone, Hash)]
+pub struct UrlMapper {
+    /// The webroot mappings, in order of precedence for equally long webroots
+    mappings: Vec<Mapping>,
+    /// The URL template for media files
+    media_template: Option<String>,
+    /// The access token that is appended to media URLs
+    media_token: Option<String>,
+    /// The signer for media URLs
+    media_signer: Option<Signer>,
+    /// Whether paths are normalized lexically instead of being canonicalized
+    lexical: bool,
+}
+impl UrlMapper {
+    /// Creates a new URL mapper that maps files within `webroot` to URLs below `base_url`
+    pub fn new(base_url: String, webroot: String) -> Self {
+        let mappings = vec![Mapping { webroot: PathBuf::from(webroot), base_url }];
+        Self { mappings, media_template: None, media_token: None, media_signer: None, lexical: false }
+    }
+
+    /// Normalizes paths lexically instead of canonicalizing them
+    ///
+    /// Lexical normalization resolves `.` and `..` without following symlinks, so files that are symlinked into the
+    /// webroot from elsewhere are mapped to their location within the webroot.
+    pub fn with_lexical_paths(mut self) -> Self {
+        self.lexical = true;
+        self
+    }
+
+    /// Sets a URL template for media files
+    ///
+    /// The template may contain the placeholders `{uuid}` for the entry UUID, `{filename}` for the escaped file name
+    /// and `{path}` for the escaped path relative to the webroot.
+    pub fn with_media_template(mut self, template: String) -> Self {
+        self.media_template = Some(template);
+        self
+    }
+
+    /// Appends the given access token as `token` query parameter to all media URLs
+    pub fn with_media_token(mut self, token: String) -> Self {
+        self.media_token = Some(token);
+        self
+    }
+
+    /// Signs all media URLs with the given signer
+    pub fn with_media_signer(mut self, signer: Signer) -> Self {
+        self.media_signer = Some(signer);
+        self
+    }
+
+    /// Creates a new URL mapper from the `FEEDME_BASE_URL` and `FEEDME_WEBROOT` environment variables
+    ///
+    /// The variables may be omitted if all playlists have their own URL mappings.
+    pub fn from_env() -> Result<Self, Error> {
+        // Load the variables from the environment
+        match (env::var("FEEDME_BASE_URL"), env::var("FEEDME_WEBROOT")) {
+            (Ok(base_url), Ok(webroot)) => Ok(Self::new(base_url, webroot)),
+            (Err(_), Err(_)) => Ok(Self {
+                mappings: Vec::new(),
+                media_template: None,
+                media_token: None,
+                media_signer: None,
+                lexical: false,
+            }),
+            (Err(_), Ok(_)) => Err(error!("missing FEEDME_BASE_URL environment variable")),
+            (Ok(_), Err(_)) => Err(error!("missing FEEDME_WEBROOT environment variable")),
+        }
+    }
+
+    /// Creates a URL mapper for the playlist in `dir`, which includes the mappings from the playlist's URL mapping file
+    ///
+    /// The playlist's mappings take precedence over equally long existing mappings.
+    pub fn for_playlist(&self, dir: &Path) -> Result<Self, Error> {
+        // Load the mapping file if any
+        let path = dir.join(URL_MAPPINGS);
+        if !path.exists() {
+            return Ok(self.clone());
+        }
+        let mappings_json = fs::read(&path).map_err(|e| error!(with: e, r#"Failed to open "{}""#, path.display()))?;
+        let mappings: BTreeMap<String, String> = serde_json::from_slice(&mappings_json)?;
+
+        // Prepend the mappings
+        let mut mapper = Self { mappings: Vec::new(), ..self.clone() };
+        for (webroot, base_url) in mappings {
+            // Validate the webroot
+            let webroot = PathBuf::from(webroot);
+            if !webroot.is_absolute() {
+                return Err(error!("webroot is not an absolute path: {} (in {})", webroot.display(), path.display()));
+            }
+            mapper.mappings.push(Mapping { webroot, base_url });
+        }
+        mapper.mappings.extend(self.mappings.iter().cloned());
+        Ok(mapper)
+    }
+
+    /// Creates an absolute URL for a file path
+    pub fn absolute_url(&self, file: &Path) -> Result<String, Error> {
+        let (mapping, path) = self.resolve(file)?;
+        match path.is_empty() {
+            true => Ok(mapping.base_url.clone()),
+            false => Ok(format!("{}/{path}", mapping.base_url)),
+        }
+    }
+
+    /// Creates the URL for the media file of a playlist entry within `dir`, applying the media URL template, access token
+    /// and signer
+    pub fn media_url(&self, dir: &Path, entry: &Entry) -> Result<String, Error> {
+        // Create the URL
+        let file = dir.join(&entry.file);
+        let mut url = match &self.media_template {
+            Some(template) => self.expand_template(template, &file, entry)?,
+            None => self.absolute_url(&file)?,
+        };
+
+        // Append the access token and sign the URL if necessary
+        if let Some(token) = &self.media_token {
+            let separator = if url.contains('?') { '&' } else { '?' };
+            url = format!("{url}{separator}token={token}");
+        }
+        match &self.media_signer {
+            Some(signer) => Ok(signer.sign(&url)),
+            None => Ok(url),
+        }
+    }
+
+    /// Creates an absolute URL for an output file that may not exist yet
+    pub fn output_url(&self, file: &Path) -> Result<String, Error> {
+        // Get the URL of the parent directory
+        let file_name = file.file_name().ok_or(error!("invalid output path: {}", file.display()))?;
+        let dir = match file.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let dir_url = self.absolute_url(dir)?;
+
+        // Append the escaped file name
+        let file_name = encode_component(file_name)?;
+        Ok(format!("{dir_url}/{file_name}"))
+    }
+
+    /// Expands the media URL template for the given file
+    fn expand_template(&self, template: &str, file: &Path, entry: &Entry) -> Result<String, Error> {
+        // Expand the simple placeholders
+        let file_name = file.file_name().ok_or(error!("invalid media path: {}", file.display()))?;
+        let mut url = template.replace("{uuid}", &entry.uuid.to_string());
+        url = url.replace("{filename}", &encode_component(file_name)?);
+
+        // Expand the path only if necessary, since the file does not need to be within a webroot otherwise
+        if url.contains("{path}") {
+            let (_, path) = self.resolve(file)?;
+            url = url.replace("{path}", &path);
+        }
+        Ok(url)
+    }
+
+    /// Finds the longest webroot that contains the file and returns it together with the escaped relative path
+    fn resolve(&self, file: &Path) -> Result<(&Mapping, String), Error> {
+        // Normalize the path and find the longest webroot that contains the file
+        let canonical = match self.lexical {
+            true => normalize_lexically(file)?,
+            false => {
+                let canonical =
+                    file.canonicalize().map_err(|e| error!(with: e, r#"Failed canonicalize "{}""#, file.display()))?;
+                strip_verbatim(canonical)
+            }
+        };
+        let mapping = self.mappings.iter().filter(|mapping| canonical.starts_with(&mapping.webroot));
+        // `max_by_key` returns the last maximum, so we reverse the mappings to prefer the earlier ones
+        let mapping = mapping.rev().max_by_key(|mapping| mapping.webroot.components().count());
+        let Some(mapping) = mapping else {
+            return Err(self.unmapped_error(&canonical));
+        };
+
+        // Escape the individual path components
+        let relative_path = canonical.strip_prefix(&mapping.webroot)?;
+        let mut path_components = Vec::new();
+        for component in relative_path.components() {
+            // Get the path component
+            let Component::Normal(component) = component else {
+                return Err(error!("unexpected path component: {component:?}"));
+            };
+
+            // Escape the path component
+            let component = encode_component(component)?;
+            path_components.push(component);
+        }
+        Ok((mapping, path_components.join("/")))
+    }
+
+    /// Creates a descriptive error for a file that is not within any webroot
+    fn unmapped_error(&self, file: &Path) -> Error {
+        if self.mappings.is_empty() {
+            let hint = format!("set FEEDME_WEBROOT and FEEDME_BASE_URL or create a {URL_MAPPINGS} file");
+            return error!("cannot create URL for {}: no webroot is configured; {hint}", file.display());
+        }
+        let webroots: Vec<_> = self.mappings.iter().map(|mapping| mapping.webroot.display().to_string()).collect();
+        error!("file is not within webroot: {} (webroots: {})", file.display(), webroots.join(", "))
+    }
+}
+
+/// Makes the path absolute and resolves `.` and `..` without following symlinks
+fn normalize_lexically(file: &Path) -> Result<PathBuf, Error> {
+    // Ensure that the file exists like `canonicalize` would
+    if !file.exists() {
+        return Err(error!("file does not exist: {}", file.display()));
+    }
+
+    // Resolve the path components
+    let absolute = env::current_dir()?.join(file);
+    let mut normalized = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    Ok(normalized)
+}
+
+/// Converts a verbatim Windows path (`\\?\C:\...`) as returned by `canonicalize` into its regular form (`C:\...`)
+fn strip_verbatim(path: PathBuf) -> PathBuf {
+    // Get the path prefix if any
+    let mut components = path.components();
+    let Some(Component::Prefix(prefix)) = components.next() else {
+        return path;
+    };
+
+    // Replace a verbatim disk prefix with the regular disk prefix
+    match prefix.kind() {
+        Prefix::VerbatimDisk(disk) => {
+            let mut regular = PathBuf::from(format!("{}:", disk as char));
+            regular.push(components.as_path());
+            regular
+        }
+        _ => path,
+    }
+}
+
+/// Percent-encodes a path component; on Unix, the raw bytes are encoded so that non-UTF-8 names are supported
+fn encode_component(component: &OsStr) -> Result<String, Error> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Ok(urlencoding::encode_binary(component.as_bytes()).into_owned())
+    }
+
+    #[cfg(not(unix))]
+    {
+        let component = component.to_str().ok_or(error!("path is not valid UTF-8"))?;
+        Ok(urlencoding::encode(component).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+    use std::fs::File;
+
+    /// Creates a file with the given name within a temporary webroot for the test and returns the webroot, the mapper
+    /// and the file path
+    fn fixture(test: &str, name: &OsStr) -> (TempDir, UrlMapper, PathBuf) {
+        // Create the webroot
+        let webroot = TempDir::new(test);
+        fs::create_dir_all(webroot.path().join("my podcast")).expect("failed to create playlist directory");
+
+        // Create the file
+        let file = webroot.path().join("my podcast").join(name);
+        File::create(&file).expect("failed to create file");
+        let urls = UrlMapper::new("https://example.org".to_string(), webroot.path().to_string_lossy().to_string());
+        (webroot, urls, file)
+    }
+
+    /// Asserts that the file name is mapped to the expected escaped URL
+    fn assert_url(test: &str, name: &OsStr, expected: &str) {
+        let (_webroot, urls, file) = fixture(test, name);
+        let url = urls.absolute_url(&file).expect("failed to create URL");
+        assert_eq!(url, format!("https://example.org/my%20podcast/{expected}"));
+
+        // Output URLs must be identical for existing files
+        let url = urls.output_url(&file).expect("failed to create output URL");
+        assert_eq!(url, format!("https://example.org/my%20podcast/{expected}"));
+    }
+
+    #[test]
+    fn spaces() {
+        assert_url("url-spaces", OsStr::new("my episode.mp4"), "my%20episode.mp4");
+    }
+
+    #[test]
+    fn unicode() {
+        assert_url("url-unicode", OsStr::new("Ümläut ☃.mp4"), "%C3%9Cml%C3%A4ut%20%E2%98%83.mp4");
+    }
+
+    #[test]
+    fn hash() {
+        assert_url("url-hash", OsStr::new("episode #1.mp4"), "episode%20%231.mp4");
+    }
+
+    #[test]
+    fn question_mark() {
+        assert_url("url-question_mark", OsStr::new("why?.mp4"), "why%3F.mp4");
+    }
+
+    #[test]
+    fn percent() {
+        assert_url("url-percent", OsStr::new("100%.mp4"), "100%25.mp4");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn non_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+        assert_url("url-non_utf8", OsStr::from_bytes(b"caf\xE9.mp4"), "caf%E9.mp4");
+    }
+
+    #[test]
+    fn outside_webroot() {
+        let (_webroot, urls, _) = fixture("url-outside_webroot", OsStr::new("outside.mp4"));
+        let error = urls.absolute_url(&env::temp_dir()).expect_err("file outside of webroot was accepted");
+        assert!(error.to_string().contains("not within webroot"));
+    }
+}
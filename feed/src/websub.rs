@@ -0,0 +1,64 @@
+//! WebSub publisher notifications
+
+use feedme_shared::{error, Error};
+use std::time::Duration;
+
+/// The timeout for hub requests
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Notifies the WebSub hub that the feed at `topic` has been updated
+pub fn publish(hub: &str, topic: &str) -> Result<(), Error> {
+    // Send the publish request
+    eprintln!("[feedme-feed] Notifying WebSub hub {hub} about {topic}");
+    let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+    let form = [("hub.mode", "publish"), ("hub.url", topic)];
+    match agent.post(hub).send_form(&form) {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(status, _)) => Err(error!("WebSub hub rejected the notification with status {status}")),
+        Err(e) => Err(error!(with: e, "failed to notify WebSub hub")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn publish_form() {
+        // Start a stub hub that accepts a single request and returns its body
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind stub hub");
+        let hub = format!("http://{}/hub", listener.local_addr().expect("stub hub has no address"));
+        let stub = thread::spawn(move || {
+            // Read the request head
+            let (stream, _) = listener.accept().expect("failed to accept connection");
+            let mut reader = BufReader::new(&stream);
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).expect("failed to read request head");
+                if line.trim_end().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().expect("invalid content length");
+                    }
+                }
+            }
+
+            // Read the body and acknowledge the notification
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).expect("failed to read request body");
+            (&stream).write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n").expect("failed to respond");
+            String::from_utf8(body).expect("request body is not UTF-8")
+        });
+
+        // Notify the hub and check the form
+        publish(&hub, "https://example.org/my podcast/feed.rss").expect("failed to notify hub");
+        let body = stub.join().expect("stub hub panicked");
+        assert_eq!(body, "hub.mode=publish&hub.url=https%3A%2F%2Fexample.org%2Fmy+podcast%2Ffeed.rss");
+    }
+}
@@ -3,8 +3,9 @@
 mod batch;
 mod meta;
 
-use feedme_shared::{error, Error};
-use std::collections::HashMap;
+use feedme_shared::{error, Error, Watcher};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
 use std::{env, process};
 
 /// Displays the error and exits with status `2`
@@ -48,7 +49,28 @@ fn main_real() -> Result<(), Error> {
     }
 
     // Create metadata files
-    batch::batch_process(args, files)
+    let watch = args.remove("watch").is_some();
+    if !watch {
+        return batch::batch_process(args, files);
+    }
+
+    // Watch the directories of the given files; the watcher reports bare file names, so compare those only
+    let (mut dirs, mut names) = (BTreeSet::new(), BTreeSet::new());
+    for file in &files {
+        let file = Path::new(file);
+        let dir = file.parent().filter(|dir| !dir.as_os_str().is_empty());
+        dirs.insert(dir.unwrap_or(Path::new(".")));
+        names.extend(file.file_name().and_then(|name| name.to_str()));
+    }
+    let mut watcher = Watcher::new(dirs)?;
+
+    // Create the metadata files whenever one of the given files changes
+    loop {
+        if let Err(e) = batch::batch_process(args.clone(), files.clone()) {
+            eprintln!("[feedme-manual] Failed to create metadata: {e}");
+        }
+        watcher.wait(|name| names.contains(name))?;
+    }
 }
 
 fn main() {
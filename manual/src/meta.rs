@@ -1,6 +1,8 @@
 //! Get file information
 
-use feedme_shared::{error, Entry, Error, UuidBuilder};
+use feedme_shared::{error, Entry, Error, Markup, UuidBuilder};
+use std::fs;
+use std::io::ErrorKind;
 use std::path::Path;
 use std::process::Command;
 use std::time::{Duration, UNIX_EPOCH};
@@ -34,11 +36,31 @@ pub fn read_metadata(file: &str) -> Result<Entry, Error> {
         _ => return Err(error!("Unknown file type")),
     };
 
+    // Read the Markdown show notes if any
+    let notes_path = file.with_extension("md");
+    let (description, markup) = match fs::read_to_string(&notes_path) {
+        Ok(notes) => (Some(notes), Markup::Markdown),
+        Err(e) if e.kind() == ErrorKind::NotFound => (None, Markup::Plain),
+        Err(e) => return Err(error!(with: e, r#"Failed to read "{}""#, notes_path.display())),
+    };
+
     // Create entry
     let file = video_name.to_string();
     let title = video_name.to_string();
-    let description = None;
-    Ok(Entry { file, uuid, size, type_, duration, date, title, description })
+    Ok(Entry {
+        file,
+        uuid,
+        size,
+        type_,
+        duration,
+        date,
+        title,
+        description,
+        markup,
+        chapters: Vec::new(),
+        author: None,
+        extractor: None,
+    })
 }
 
 /// Uses ffprobe to get the file duration
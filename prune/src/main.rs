@@ -0,0 +1,67 @@
+#![doc = include_str!("../README.md")]
+
+mod prune;
+
+use crate::prune::Options;
+use feedme_feed::retention::Retention;
+use feedme_shared::{error, Error};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{env, process};
+
+/// Displays the error and exits with status `2`
+fn exit_error(e: Error) -> ! {
+    // Print the error
+    eprintln!("Fatal error: {e}");
+
+    // Print the backtrace if any
+    if e.has_backtrace() {
+        eprintln!();
+        eprintln!("{}", e.backtrace);
+    }
+
+    // Print general help
+    eprintln!("---");
+    eprint!("{}", include_str!("../HELP.txt"));
+    process::exit(1);
+}
+
+/// The fallible, real main function
+fn main_real() -> Result<(), Error> {
+    // Parse arguments
+    let mut args = HashMap::new();
+    for arg in env::args().skip(1) {
+        // Check if we have a key-value arg
+        let Some(kv_arg) = arg.strip_prefix("--") else {
+            return Err(error!("unexpected argument: {arg}"));
+        };
+
+        // Split argument
+        let (key, value) = kv_arg.split_once('=').unwrap_or((kv_arg, ""));
+        args.insert(key.to_string(), value.to_string());
+    }
+
+    // Get the options
+    let options = Options {
+        quarantine: args.remove("quarantine").map(PathBuf::from),
+        renumber: args.remove("renumber").is_some(),
+        dry_run: args.remove("dry-run").is_some(),
+        retention: Retention {
+            keep: args.remove("keep").map(|keep| keep.parse()).transpose()?,
+            max_age: args.remove("max-age").map(|max_age| max_age.parse()).transpose()?,
+        },
+    };
+    if let Some(arg) = args.keys().next() {
+        return Err(error!("unexpected argument: --{arg}"));
+    }
+
+    // Prune the playlist
+    prune::prune(Path::new("."), &options)
+}
+
+fn main() {
+    // Print error information in case of a failure
+    if let Err(e) = main_real() {
+        exit_error(e);
+    }
+}
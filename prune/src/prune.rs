@@ -0,0 +1,158 @@
+//! Removes dangling and expired playlist entries
+
+use feedme_feed::meta;
+use feedme_feed::retention::Retention;
+use feedme_shared::{error, Error};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The extensions of companion files that belong to a media file (e.g. thumbnails or yt-dlp metadata)
+const COMPANION_EXTENSIONS: [&str; 5] = ["jpg", "jpeg", "png", "webp", "info.json"];
+
+/// The prune options
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Moves the removed files into this directory instead of deleting them
+    pub quarantine: Option<PathBuf>,
+    /// Whether to renumber the remaining entries to close the gaps
+    pub renumber: bool,
+    /// Whether to only list the actions without touching any file
+    pub dry_run: bool,
+    /// The retention policy; expired entries are removed together with their media and companion files
+    pub retention: Retention,
+}
+
+/// Removes all entries in `dir` whose referenced file does not exist, as well as all entries that have expired
+pub fn prune(dir: &Path, options: &Options) -> Result<(), Error> {
+    // Find the dangling entries
+    let (mut dangling, mut existing) = (Vec::new(), Vec::new());
+    for (name, entry) in meta::collect_entries(dir)? {
+        match dir.join(&entry.file).exists() {
+            true => existing.push((name, entry)),
+            false => dangling.push((name, entry)),
+        }
+    }
+
+    // Find the expired entries
+    let is_expired = options.retention.expired(existing.iter().map(|(_, entry)| entry))?;
+    let (mut expired, mut remaining) = (Vec::new(), Vec::new());
+    for ((name, entry), is_expired) in existing.into_iter().zip(is_expired) {
+        match is_expired {
+            true => expired.push((name, entry)),
+            false => remaining.push(name),
+        }
+    }
+
+    // Plan the renames before touching any file, so that a conflict does not leave the directory half pruned
+    let mut renames = Vec::new();
+    if options.renumber {
+        let removed: BTreeSet<_> = dangling.iter().chain(&expired).map(|(name, _)| name.as_str()).collect();
+        renames = renumber(dir, &remaining, &removed)?;
+    }
+
+    // Remove the dangling entries
+    for (name, entry) in dangling {
+        eprintln!("[feedme-prune] Found dangling entry {name} for missing file: {}", entry.file);
+        remove_file(dir, &name, options)?;
+    }
+
+    // Remove the expired entries together with their media and companion files
+    for (name, entry) in expired {
+        eprintln!("[feedme-prune] Found expired entry {name} for file: {}", entry.file);
+        for companion in companion_files(dir, &entry.file) {
+            remove_file(dir, &companion, options)?;
+        }
+        remove_file(dir, &entry.file, options)?;
+        remove_file(dir, &name, options)?;
+    }
+
+    // Close the gaps
+    for (name, new_name) in renames {
+        eprintln!("[feedme-prune] Renaming {name} to {new_name}");
+        if !options.dry_run {
+            fs::rename(dir.join(&name), dir.join(&new_name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Collects all existing companion files of a media file
+fn companion_files(dir: &Path, file: &str) -> Vec<String> {
+    // Get the file name without extension
+    let stem = match file.rsplit_once('.') {
+        Some((stem, _)) => stem,
+        None => file,
+    };
+
+    // Collect the existing companion files
+    let companions = COMPANION_EXTENSIONS.iter().map(|extension| format!("{stem}.{extension}"));
+    companions.filter(|companion| dir.join(companion).is_file()).collect()
+}
+
+/// Removes or quarantines a file
+fn remove_file(dir: &Path, name: &str, options: &Options) -> Result<(), Error> {
+    let path = dir.join(name);
+    match &options.quarantine {
+        Some(quarantine) => {
+            eprintln!("[feedme-prune] Moving {} to {}", path.display(), quarantine.display());
+            if !options.dry_run {
+                let file_name = path.file_name().ok_or(error!("invalid file name: {name}"))?;
+                fs::create_dir_all(quarantine)?;
+                fs::rename(&path, quarantine.join(file_name))?;
+            }
+        }
+        None => {
+            eprintln!("[feedme-prune] Removing {}", path.display());
+            if !options.dry_run {
+                fs::remove_file(&path)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Plans the renames that number the given entries consecutively in numeric order, starting with the lowest number
+///
+/// All numbers are padded to the widest number among the entries (e.g. the ten digits of dated yt-dlp entries), so
+/// that the lexical order of the names keeps matching the numeric order.
+///
+/// Every target is checked up front: it must neither be held by another entry at the time of the rename nor by any
+/// other file that is not going to be removed.
+fn renumber(dir: &Path, names: &[String], removed: &BTreeSet<&str>) -> Result<Vec<(String, String)>, Error> {
+    // Parse the entry numbers and their width and sort the entries numerically
+    let (mut numbered, mut width) = (Vec::new(), 0);
+    for name in names {
+        let digits = name.strip_prefix("playlist-entry").and_then(|name| name.strip_suffix(".feedme"));
+        let number: u64 = digits.and_then(|digits| digits.parse().ok()).ok_or(error!("invalid entry name: {name}"))?;
+        width = width.max(digits.map(str::len).unwrap_or_default());
+        numbered.push((number, name));
+    }
+    numbered.sort();
+    let Some((first, _)) = numbered.first().copied() else {
+        return Ok(Vec::new());
+    };
+
+    // Plan the renames in ascending order; since each entry only moves to a lower number, entries usually free their
+    // slot before it is needed
+    let mut taken: BTreeSet<&str> = names.iter().map(String::as_str).collect();
+    let mut renames = Vec::new();
+    for (offset, (_, name)) in (0..).zip(numbered) {
+        // Skip entries that are already at the right position
+        let new_name = format!("playlist-entry{:0width$}.feedme", first + offset);
+        if &new_name == name {
+            continue;
+        }
+
+        // Ensure that the rename does not overwrite anything
+        let is_foreign = !names.contains(&new_name) && !removed.contains(new_name.as_str());
+        if taken.contains(new_name.as_str()) || (is_foreign && dir.join(&new_name).exists()) {
+            return Err(error!("cannot rename {name} to {new_name}: the target already exists"));
+        }
+
+        // Record the rename
+        taken.remove(name.as_str());
+        renames.push((name.clone(), new_name));
+    }
+    Ok(renames)
+}
@@ -0,0 +1,290 @@
+//! A minimal HTTP/1.1 implementation
+
+use feedme_shared::{error, Error};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, Read, Write};
+use std::time::SystemTime;
+use time::format_description::FormatItem;
+use time::macros::format_description;
+use time::{OffsetDateTime, PrimitiveDateTime};
+
+/// The HTTP date format (IMF-fixdate)
+const DATE_FORMAT: &[FormatItem] =
+    format_description!("[weekday repr:short], [day] [month repr:short] [year] [hour]:[minute]:[second] GMT");
+
+/// The maximum amount of header lines we accept
+const HEADERS_MAX: usize = 128;
+
+/// An HTTP request
+#[derive(Debug, Clone)]
+pub struct Request {
+    /// The request method
+    pub method: String,
+    /// The request target
+    pub target: String,
+    /// The request headers with lowercase names
+    pub headers: HashMap<String, String>,
+}
+impl Request {
+    /// Reads a request head from the stream
+    pub fn read<T>(stream: &mut T) -> Result<Self, Error>
+    where
+        T: BufRead,
+    {
+        // Read the request line
+        let request_line = Self::read_line(stream)?;
+        let mut request_line = request_line.split(' ');
+        let (Some(method), Some(target), Some(_version), None) =
+            (request_line.next(), request_line.next(), request_line.next(), request_line.next())
+        else {
+            return Err(error!("invalid HTTP request line"));
+        };
+
+        // Read the headers
+        let mut headers = HashMap::new();
+        'read_headers: for _ in 0..HEADERS_MAX {
+            // Read the next header line
+            let header = Self::read_line(stream)?;
+            if header.is_empty() {
+                let (method, target) = (method.to_string(), target.to_string());
+                return Ok(Self { method, target, headers });
+            }
+
+            // Split the header
+            let Some((name, value)) = header.split_once(':') else {
+                continue 'read_headers;
+            };
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+        Err(error!("too many HTTP headers"))
+    }
+
+    /// Gets the value of the header with the given lowercase name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+
+    /// Reads a single CRLF-terminated line
+    fn read_line<T>(stream: &mut T) -> Result<String, Error>
+    where
+        T: BufRead,
+    {
+        // Read the line with a sane limit
+        let mut line = Vec::new();
+        stream.take(8192).read_until(b'\n', &mut line)?;
+        let Some(b'\n') = line.pop() else {
+            return Err(error!("truncated HTTP request"));
+        };
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        Ok(String::from_utf8(line)?)
+    }
+}
+
+/// An HTTP response body
+#[derive(Debug)]
+pub enum Body {
+    /// An empty body
+    Empty,
+    /// An in-memory body
+    Bytes(Vec<u8>),
+    /// The given amount of bytes from the current position within the file
+    File(File, u64),
+}
+impl Body {
+    /// The length of the body
+    pub fn len(&self) -> u64 {
+        match self {
+            Self::Empty => 0,
+            Self::Bytes(bytes) => bytes.len() as u64,
+            Self::File(_, len) => *len,
+        }
+    }
+}
+
+/// An HTTP response
+#[derive(Debug)]
+pub struct Response {
+    /// The status code
+    pub status: u16,
+    /// The reason phrase
+    pub reason: &'static str,
+    /// The response headers
+    pub headers: Vec<(&'static str, String)>,
+    /// The response body
+    pub body: Body,
+}
+impl Response {
+    /// Creates a new response with an empty body
+    pub fn new(status: u16, reason: &'static str) -> Self {
+        Self { status, reason, headers: Vec::new(), body: Body::Empty }
+    }
+
+    /// Adds a header
+    pub fn header<T>(mut self, name: &'static str, value: T) -> Self
+    where
+        T: ToString,
+    {
+        self.headers.push((name, value.to_string()));
+        self
+    }
+
+    /// Sets the body
+    pub fn body(mut self, body: Body) -> Self {
+        self.body = body;
+        self
+    }
+
+    /// Writes the response to the stream; if `head_only` is set, the body is omitted
+    pub fn write<T>(self, stream: &mut T, head_only: bool) -> Result<(), Error>
+    where
+        T: Write,
+    {
+        // Write the head
+        write!(stream, "HTTP/1.1 {} {}\r\n", self.status, self.reason)?;
+        for (name, value) in &self.headers {
+            write!(stream, "{name}: {value}\r\n")?;
+        }
+        if self.status != 304 {
+            // A 304 response has no body, and its length would be mistaken for the length of the resource
+            write!(stream, "Content-Length: {}\r\n", self.body.len())?;
+        }
+        write!(stream, "Connection: close\r\n")?;
+        write!(stream, "\r\n")?;
+
+        // Write the body
+        match self.body {
+            _ if head_only => (),
+            Body::Empty => (),
+            Body::Bytes(bytes) => stream.write_all(&bytes)?,
+            Body::File(file, len) => {
+                let copied = io::copy(&mut file.take(len), stream)?;
+                if copied != len {
+                    return Err(error!("file has been truncated while sending"));
+                }
+            }
+        }
+        stream.flush()?;
+        Ok(())
+    }
+}
+
+/// A byte range request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Range {
+    /// The entire resource
+    Full,
+    /// The inclusive byte range `start..=end`
+    Partial(u64, u64),
+    /// The range is not satisfiable
+    Unsatisfiable,
+}
+impl Range {
+    /// Parses a `Range` header value for a resource of the given length
+    ///
+    /// Multiple ranges and malformed values are ignored, i.e. the entire resource is served.
+    pub fn parse(range: Option<&str>, len: u64) -> Self {
+        // Get the byte range
+        let Some(range) = range.and_then(|range| range.trim().strip_prefix("bytes=")) else {
+            return Self::Full;
+        };
+        let Some((start, end)) = range.split_once('-') else {
+            return Self::Full;
+        };
+        if range.contains(',') {
+            return Self::Full;
+        }
+
+        // Parse the range
+        let (start, end) = match (start.trim(), end.trim()) {
+            ("", "") => return Self::Full,
+            ("", suffix) => match suffix.parse::<u64>() {
+                Ok(0) => return Self::Unsatisfiable,
+                Ok(suffix) => (len.saturating_sub(suffix), len.saturating_sub(1)),
+                Err(_) => return Self::Full,
+            },
+            (start, "") => match start.parse::<u64>() {
+                Ok(start) => (start, len.saturating_sub(1)),
+                Err(_) => return Self::Full,
+            },
+            (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+                (Ok(start), Ok(end)) if start <= end => (start, end.min(len.saturating_sub(1))),
+                _ => return Self::Full,
+            },
+        };
+
+        // Validate the range
+        match start < len {
+            true => Self::Partial(start, end),
+            false => Self::Unsatisfiable,
+        }
+    }
+}
+
+/// Formats a timestamp as HTTP date
+pub fn format_date(time: SystemTime) -> Result<String, Error> {
+    let time = OffsetDateTime::from(time);
+    Ok(time.format(DATE_FORMAT)?)
+}
+
+/// Parses an HTTP date
+pub fn parse_date(date: &str) -> Option<SystemTime> {
+    let date = PrimitiveDateTime::parse(date, DATE_FORMAT).ok()?;
+    Some(date.assume_utc().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Body, Range, Response};
+
+    #[test]
+    fn range_open_end() {
+        assert_eq!(Range::parse(Some("bytes=0-"), 100), Range::Partial(0, 99));
+        assert_eq!(Range::parse(Some("bytes=42-"), 100), Range::Partial(42, 99));
+        assert_eq!(Range::parse(Some("bytes=0-"), 0), Range::Unsatisfiable);
+    }
+
+    #[test]
+    fn range_suffix() {
+        assert_eq!(Range::parse(Some("bytes=-10"), 100), Range::Partial(90, 99));
+        assert_eq!(Range::parse(Some("bytes=-100"), 100), Range::Partial(0, 99));
+        assert_eq!(Range::parse(Some("bytes=-500"), 100), Range::Partial(0, 99));
+        assert_eq!(Range::parse(Some("bytes=-0"), 100), Range::Unsatisfiable);
+    }
+
+    #[test]
+    fn range_start_after_end() {
+        assert_eq!(Range::parse(Some("bytes=50-10"), 100), Range::Full);
+    }
+
+    #[test]
+    fn range_past_eof() {
+        assert_eq!(Range::parse(Some("bytes=90-200"), 100), Range::Partial(90, 99));
+        assert_eq!(Range::parse(Some("bytes=100-200"), 100), Range::Unsatisfiable);
+        assert_eq!(Range::parse(Some("bytes=100-"), 100), Range::Unsatisfiable);
+    }
+
+    #[test]
+    fn range_ignored() {
+        assert_eq!(Range::parse(None, 100), Range::Full);
+        assert_eq!(Range::parse(Some("bytes=-"), 100), Range::Full);
+        assert_eq!(Range::parse(Some("bytes=0-1,5-6"), 100), Range::Full);
+        assert_eq!(Range::parse(Some("items=0-1"), 100), Range::Full);
+        assert_eq!(Range::parse(Some("bytes=a-b"), 100), Range::Full);
+    }
+
+    #[test]
+    fn content_length() {
+        let mut ok = Vec::new();
+        let response = Response::new(200, "OK").body(Body::Bytes(b"feed".to_vec()));
+        response.write(&mut ok, false).expect("failed to write response");
+        assert!(String::from_utf8_lossy(&ok).contains("Content-Length: 4\r\n"));
+
+        // Not modified responses must not announce the length of their empty body
+        let mut not_modified = Vec::new();
+        Response::new(304, "Not Modified").write(&mut not_modified, false).expect("failed to write response");
+        assert!(!String::from_utf8_lossy(&not_modified).contains("Content-Length"));
+    }
+}
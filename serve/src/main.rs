@@ -0,0 +1,91 @@
+#![doc = include_str!("../README.md")]
+
+mod http;
+mod server;
+
+use crate::server::{ConnectionLimit, Server};
+use feedme_shared::{error, Error};
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::{env, process, thread};
+
+/// The maximum amount of connections that are handled concurrently
+const CONNECTIONS_MAX: usize = 64;
+
+/// Displays the error and exits with status `2`
+fn exit_error(e: Error) -> ! {
+    // Print the error
+    eprintln!("Fatal error: {e}");
+
+    // Print the backtrace if any
+    if e.has_backtrace() {
+        eprintln!();
+        eprintln!("{}", e.backtrace);
+    }
+
+    // Print general help
+    eprintln!("---");
+    eprint!("{}", include_str!("../HELP.txt"));
+    process::exit(1);
+}
+
+/// The fallible, real main function
+fn main_real() -> Result<(), Error> {
+    // Parse arguments
+    let mut args = HashMap::new();
+    for arg in env::args().skip(1) {
+        // Check if we have a key-value arg
+        let Some(kv_arg) = arg.strip_prefix("--") else {
+            return Err(error!("unexpected argument: {arg}"));
+        };
+
+        // Split argument
+        let (key, value) = kv_arg.split_once('=').unwrap_or((kv_arg, ""));
+        args.insert(key.to_string(), value.to_string());
+    }
+
+    // Get the configuration
+    let listen = args.remove("listen").unwrap_or_else(|| "127.0.0.1:8080".to_string());
+    let feed_name = args.remove("feed").unwrap_or_else(|| "feed.rss".to_string());
+    let webroot = args.remove("webroot").or_else(|| env::var("FEEDME_WEBROOT").ok()).unwrap_or_else(|| ".".to_string());
+    let webroot = PathBuf::from(webroot).canonicalize()?;
+    let base_url = env::var("FEEDME_BASE_URL").ok();
+    let lexical_paths = args.remove("lexical-paths").is_some();
+    if let Some(arg) = args.keys().next() {
+        return Err(error!("unexpected argument: --{arg}"));
+    }
+
+    // Start the server
+    let listener = TcpListener::bind(&listen).map_err(|e| error!(with: e, "Failed to listen on {listen}"))?;
+    let server = Arc::new(Server::new(webroot, feed_name, base_url, lexical_paths));
+    eprintln!("[feedme-serve] Listening on http://{}", listener.local_addr()?);
+
+    // Handle the connections
+    let limit = Arc::new(ConnectionLimit::new(CONNECTIONS_MAX));
+    for stream in listener.incoming() {
+        // Unwrap the connection or skip it
+        let Ok(stream) = stream else {
+            continue;
+        };
+
+        // Wait for a free slot and handle the connection
+        let slot = ConnectionLimit::acquire(&limit);
+        let server = Arc::clone(&server);
+        thread::spawn(move || {
+            let _slot = slot;
+            if let Err(e) = server.handle(stream) {
+                eprintln!("[feedme-serve] Connection error: {e}");
+            }
+        });
+    }
+    Ok(())
+}
+
+fn main() {
+    // Print error information in case of a failure
+    if let Err(e) = main_real() {
+        exit_error(e);
+    }
+}
@@ -0,0 +1,365 @@
+//! Serves the webroot
+
+use crate::http::{self, Body, Range, Request, Response};
+use feedme_feed::meta::{self, PLAYLIST_META};
+use feedme_feed::rss::{self, AtomLink, Document};
+use feedme_feed::url::UrlMapper;
+use feedme_shared::{error, mime, Error};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, Seek, SeekFrom};
+use std::net::TcpStream;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The timeout for reading from and writing to a connection
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A file to serve
+#[derive(Debug)]
+struct Resource {
+    /// The MIME type
+    type_: String,
+    /// The entity tag
+    etag: String,
+    /// The modification time
+    modified: SystemTime,
+    /// The resource length
+    len: u64,
+    /// The resource body
+    body: Body,
+}
+
+/// The MIME types of the media files of a playlist
+#[derive(Debug)]
+struct MediaTypes {
+    /// The most recent modification time of the metadata files the types have been read from
+    modified: SystemTime,
+    /// The MIME types by media file path
+    types: HashMap<PathBuf, String>,
+}
+
+/// Limits the amount of concurrently handled connections
+#[derive(Debug)]
+pub struct ConnectionLimit {
+    /// The amount of free slots
+    free: Mutex<usize>,
+    /// Signals that a slot has been released
+    released: Condvar,
+}
+impl ConnectionLimit {
+    /// Creates a new limit with the given amount of slots
+    pub fn new(slots: usize) -> Self {
+        Self { free: Mutex::new(slots), released: Condvar::new() }
+    }
+
+    /// Blocks until a slot is free and takes it; the slot is released when the returned guard is dropped
+    pub fn acquire(this: &Arc<Self>) -> ConnectionSlot {
+        let mut free = this.free.lock().expect("connection limit is poisoned");
+        while *free == 0 {
+            free = this.released.wait(free).expect("connection limit is poisoned");
+        }
+        *free -= 1;
+        ConnectionSlot { limit: Arc::clone(this) }
+    }
+}
+
+/// A taken connection slot
+#[derive(Debug)]
+pub struct ConnectionSlot {
+    /// The limit the slot belongs to
+    limit: Arc<ConnectionLimit>,
+}
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        let mut free = self.limit.free.lock().expect("connection limit is poisoned");
+        *free += 1;
+        self.limit.released.notify_one();
+    }
+}
+
+/// The webroot server
+#[derive(Debug)]
+pub struct Server {
+    /// The webroot directory
+    webroot: PathBuf,
+    /// The file name of the feeds that are regenerated on demand
+    feed_name: String,
+    /// The base URL for generated feeds; if unset, the URL is derived from the `Host` header
+    base_url: Option<String>,
+    /// Whether paths within generated feeds are normalized lexically instead of being canonicalized
+    lexical_paths: bool,
+    /// The media types of the playlists by playlist directory
+    media_types: Mutex<HashMap<PathBuf, MediaTypes>>,
+}
+impl Server {
+    /// Creates a new server for the given webroot
+    pub fn new(webroot: PathBuf, feed_name: String, base_url: Option<String>, lexical_paths: bool) -> Self {
+        Self { webroot, feed_name, base_url, lexical_paths, media_types: Mutex::default() }
+    }
+
+    /// Handles a single connection
+    pub fn handle(&self, stream: TcpStream) -> Result<(), Error> {
+        // Limit the time a client may stall the connection
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+        // Read the request
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let request = Request::read(&mut reader)?;
+
+        // Create the response
+        let response = match self.respond(&request) {
+            Ok(response) => response,
+            Err(e) => {
+                eprintln!("[feedme-serve] Failed to serve {}: {e}", request.target);
+                Response::new(500, "Internal Server Error")
+            }
+        };
+
+        // Send the response
+        eprintln!("[feedme-serve] {} {} -> {}", request.method, request.target, response.status);
+        let head_only = request.method == "HEAD";
+        response.write(&mut &stream, head_only)
+    }
+
+    /// Creates the response for a request
+    fn respond(&self, request: &Request) -> Result<Response, Error> {
+        // Validate the method
+        if request.method != "GET" && request.method != "HEAD" {
+            return Ok(Response::new(405, "Method Not Allowed").header("Allow", "GET, HEAD"));
+        }
+
+        // Resolve the path
+        let Some(mut path) = self.resolve(&request.target) else {
+            return Ok(Response::new(404, "Not Found"));
+        };
+        if path.is_dir() {
+            path.push("index.html");
+        }
+
+        // Load the resource
+        let resource = match self.is_feed(&path) {
+            true => self.load_feed(&path, request)?,
+            false if path.is_file() => self.load_file(&path)?,
+            false => return Ok(Response::new(404, "Not Found")),
+        };
+        Self::respond_resource(resource, request)
+    }
+
+    /// Creates the response for a resource, taking conditional and range requests into account
+    fn respond_resource(mut resource: Resource, request: &Request) -> Result<Response, Error> {
+        // Check if the client has an up-to-date copy
+        let not_modified = match (request.header("if-none-match"), request.header("if-modified-since")) {
+            (Some(etags), _) => etags.split(',').any(|etag| etag.trim() == resource.etag || etag.trim() == "*"),
+            (None, Some(since)) => http::parse_date(since).is_some_and(|since| truncate(resource.modified) <= since),
+            (None, None) => false,
+        };
+
+        // Create the common response head
+        let last_modified = http::format_date(resource.modified)?;
+        let response = |status, reason| {
+            Response::new(status, reason)
+                .header("Content-Type", &resource.type_)
+                .header("ETag", &resource.etag)
+                .header("Last-Modified", &last_modified)
+                .header("Accept-Ranges", "bytes")
+        };
+        if not_modified {
+            return Ok(response(304, "Not Modified"));
+        }
+
+        // Ignore the range if the resource has changed
+        let mut range = Range::parse(request.header("range"), resource.len);
+        if request.header("if-range").is_some_and(|if_range| if_range != resource.etag) {
+            range = Range::Full;
+        }
+
+        // Select the requested range
+        match range {
+            Range::Full => Ok(response(200, "OK").body(resource.body)),
+            Range::Unsatisfiable => {
+                let content_range = format!("bytes */{}", resource.len);
+                Ok(Response::new(416, "Range Not Satisfiable").header("Content-Range", content_range))
+            }
+            Range::Partial(start, end) => {
+                // Slice the body
+                let len = end - start + 1;
+                resource.body = match resource.body {
+                    Body::Bytes(bytes) => Body::Bytes(bytes[start as usize..=end as usize].to_vec()),
+                    Body::File(mut file, _) => {
+                        file.seek(SeekFrom::Start(start))?;
+                        Body::File(file, len)
+                    }
+                    Body::Empty => Body::Empty,
+                };
+
+                // Create the response
+                let content_range = format!("bytes {start}-{end}/{}", resource.len);
+                Ok(response(206, "Partial Content").header("Content-Range", content_range).body(resource.body))
+            }
+        }
+    }
+
+    /// Resolves the request target to a path within the webroot; hidden files and directories (e.g. `.git` or the
+    /// `.feedme-cache`) cannot be resolved
+    fn resolve(&self, target: &str) -> Option<PathBuf> {
+        // Strip the query and decode the path
+        let path = target.split(['?', '#']).next().unwrap_or_default();
+        let path = path.strip_prefix('/')?;
+        let relative = decode_path(path)?;
+
+        // Ensure that the path does not escape the webroot and does not reference a hidden file
+        let is_visible = relative.components().all(|component| match component {
+            Component::Normal(name) => !name.as_encoded_bytes().starts_with(b"."),
+            _ => false,
+        });
+        is_visible.then(|| self.webroot.join(relative))
+    }
+
+    /// Whether the path references a feed that is generated on demand
+    fn is_feed(&self, path: &Path) -> bool {
+        let is_feed_name = path.file_name().is_some_and(|name| name == self.feed_name.as_str());
+        let has_playlist = path.parent().is_some_and(|dir| dir.join(PLAYLIST_META).is_file());
+        is_feed_name && has_playlist
+    }
+
+    /// Generates a feed from the playlist metadata
+    fn load_feed(&self, path: &Path, request: &Request) -> Result<Resource, Error> {
+        // Derive the base URL from the request if necessary
+        let base_url = match (&self.base_url, request.header("host")) {
+            (Some(base_url), _) => base_url.clone(),
+            (None, Some(host)) => format!("http://{host}"),
+            (None, None) => return Err(error!("cannot derive base URL without Host header")),
+        };
+        let webroot = self.webroot.to_str().ok_or(error!("webroot is not valid UTF-8"))?;
+
+        // Build the feed
+        let dir = path.parent().ok_or(error!("feed has no parent directory"))?;
+        let mut urls = UrlMapper::new(base_url, webroot.to_string()).for_playlist(dir)?;
+        if self.lexical_paths {
+            urls = urls.with_lexical_paths();
+        }
+        let (playlist, entries) = meta::collect_metadata(dir)?;
+        let modified = Self::metadata_modified(dir)?;
+        let build_date = modified.duration_since(UNIX_EPOCH)?.as_secs();
+        let links = vec![AtomLink::feed("self", urls.output_url(path)?)];
+        let document = Document { links, build_date, ..Document::new(&entries) };
+        let feed = rss::build_feed(dir, &playlist, &document, None, &urls)?;
+
+        // Create the resource
+        let mut hasher = DefaultHasher::new();
+        feed.hash(&mut hasher);
+        let etag = format!(r#""{:016x}""#, hasher.finish());
+        let len = feed.len() as u64;
+        Ok(Resource { type_: "application/rss+xml".to_string(), etag, modified, len, body: Body::Bytes(feed) })
+    }
+
+    /// Opens a static file
+    fn load_file(&self, path: &Path) -> Result<Resource, Error> {
+        // Get the file metadata
+        let file = File::open(path)?;
+        let metadata = file.metadata()?;
+        let (len, modified) = (metadata.len(), metadata.modified()?);
+
+        // Create the resource
+        let modified_secs = modified.duration_since(UNIX_EPOCH)?.as_secs();
+        let etag = format!(r#""{len:x}-{modified_secs:x}""#);
+        let type_ = self.content_type(path)?;
+        Ok(Resource { type_, etag, modified, len, body: Body::File(file, len) })
+    }
+
+    /// Gets the most recent modification time of all feedme metadata files within the directory
+    fn metadata_modified(dir: &Path) -> Result<SystemTime, Error> {
+        let mut modified = UNIX_EPOCH;
+        'read_dir: for entry in fs::read_dir(dir)? {
+            // Unwrap the entry or skip it
+            let Ok(entry) = entry else {
+                continue 'read_dir;
+            };
+
+            // Check if the entry is a feedme metadata file
+            let is_feedme = entry.file_name().to_string_lossy().ends_with(".feedme");
+            if is_feedme {
+                modified = modified.max(entry.metadata()?.modified()?);
+            }
+        }
+        Ok(modified)
+    }
+
+    /// Gets the MIME type of a file, preferring the type from the playlist entry that references it
+    fn content_type(&self, path: &Path) -> Result<String, Error> {
+        // Check if the file is referenced by a playlist entry
+        if let Some(dir) = path.parent().filter(|dir| dir.join(PLAYLIST_META).is_file()) {
+            if let Some(type_) = self.media_type(dir, path)? {
+                return Ok(type_);
+            }
+        }
+
+        // Guess the type from the extension
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+        Ok(mime::for_extension(extension).to_string())
+    }
+
+    /// Gets the MIME type of a media file from the playlist in `dir`; the playlist metadata is only read again if it
+    /// has changed since the last lookup
+    fn media_type(&self, dir: &Path, path: &Path) -> Result<Option<String>, Error> {
+        // Rescan the playlist if the metadata has changed
+        let modified = Self::metadata_modified(dir)?;
+        let mut media_types = self.media_types.lock().expect("media type cache is poisoned");
+        let is_current = media_types.get(dir).is_some_and(|media_types| media_types.modified == modified);
+        if !is_current {
+            // Map the media files to their types; invalid metadata just yields no types
+            let entries = meta::collect_metadata(dir).map(|(_, entries)| entries).unwrap_or_default();
+            let types = entries.into_iter().map(|entry| (dir.join(&entry.file), entry.type_)).collect();
+            media_types.insert(dir.to_path_buf(), MediaTypes { modified, types });
+        }
+
+        // Get the type
+        let type_ = media_types.get(dir).and_then(|media_types| media_types.types.get(path));
+        Ok(type_.cloned())
+    }
+}
+
+/// Truncates a timestamp to whole seconds as used by HTTP dates
+fn truncate(time: SystemTime) -> SystemTime {
+    let secs = time.duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or_default();
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+/// Percent-decodes a URL path; on Unix, the decoded bytes do not need to be valid UTF-8
+fn decode_path(path: &str) -> Option<PathBuf> {
+    let path = urlencoding::decode_binary(path.as_bytes());
+
+    #[cfg(unix)]
+    {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        Some(PathBuf::from(OsStr::from_bytes(&path)))
+    }
+
+    #[cfg(not(unix))]
+    {
+        String::from_utf8(path.into_owned()).ok().map(PathBuf::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve() {
+        let server = Server::new(PathBuf::from("/var/www"), "feed.rss".to_string(), None, false);
+        assert_eq!(server.resolve("/pod/ep%201.mp4?token=abc"), Some(PathBuf::from("/var/www/pod/ep 1.mp4")));
+
+        // Paths must not escape the webroot or reference hidden files
+        assert_eq!(server.resolve("/pod/../../etc/passwd"), None);
+        assert_eq!(server.resolve("/pod/.feedme-cache"), None);
+        assert_eq!(server.resolve("/.git/config"), None);
+        assert_eq!(server.resolve("/pod/%2Efeedme-cache"), None);
+    }
+}
@@ -2,8 +2,11 @@
 
 pub mod error;
 pub mod metadata;
+pub mod mime;
 pub mod uuid;
+pub mod watch;
 
 pub use crate::error::Error;
-pub use crate::metadata::{Entry, Playlist};
+pub use crate::metadata::{Chapter, Entry, Markup, Playlist};
 pub use crate::uuid::{Uuid, UuidBuilder};
+pub use crate::watch::Watcher;
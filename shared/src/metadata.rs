@@ -23,6 +23,101 @@ pub struct Entry {
     pub title: String,
     /// The item description
     pub description: Option<String>,
+    /// The markup language of the item description
+    #[serde(default, skip_serializing_if = "Markup::is_plain")]
+    pub markup: Markup,
+    /// The chapter markers of the entry
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chapters: Vec<Chapter>,
+    /// The entry author (e.g. the uploader or artist)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// The yt-dlp extractor that downloaded the entry (e.g. `youtube` or `vimeo`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extractor: Option<String>,
+}
+
+/// A chapter marker
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Chapter {
+    /// The chapter start in seconds
+    pub start: u64,
+    /// The human readable chapter title
+    pub title: String,
+}
+impl Chapter {
+    /// The minimum number of chapters within a description
+    const MIN_DESCRIPTION_CHAPTERS: usize = 3;
+
+    /// Parses the chapter markers from `[h:]mm:ss Title` lines within a description
+    ///
+    /// Like on YouTube, the lines are only treated as chapters if there are at least three of them, the first one starts
+    /// at `0:00` and the start times are ascending; otherwise no chapters are returned.
+    pub fn from_description(description: &str) -> Vec<Self> {
+        // Collect all chapter lines
+        let mut chapters: Vec<Self> = Vec::new();
+        for line in description.lines() {
+            // Split the timestamp from the title
+            let Some((timestamp, title)) = line.trim().split_once(char::is_whitespace) else {
+                continue;
+            };
+            let Some(start) = Self::parse_timestamp(timestamp) else {
+                continue;
+            };
+
+            // Strip common separators from the title
+            let title = title.trim_start_matches(|char: char| char.is_whitespace() || "-–—:|".contains(char));
+            if !title.is_empty() {
+                chapters.push(Self { start, title: title.trim_end().to_string() });
+            }
+        }
+
+        // Validate the chapters
+        let starts_at_zero = chapters.first().is_some_and(|chapter| chapter.start == 0);
+        let is_ascending = chapters.windows(2).all(|pair| pair[0].start < pair[1].start);
+        match chapters.len() >= Self::MIN_DESCRIPTION_CHAPTERS && starts_at_zero && is_ascending {
+            true => chapters,
+            false => Vec::new(),
+        }
+    }
+
+    /// Parses a `[h:]mm:ss` timestamp into seconds
+    pub fn parse_timestamp(timestamp: &str) -> Option<u64> {
+        // Validate the format
+        let components: Vec<_> = timestamp.split(':').collect();
+        let is_numeric =
+            |component: &&str| !component.is_empty() && component.bytes().all(|byte| byte.is_ascii_digit());
+        if !(2..=3).contains(&components.len()) || !components.iter().all(is_numeric) {
+            return None;
+        }
+
+        // Parse the components; minutes and seconds must be below 60 if they are followed by a larger unit
+        let mut values = components.iter().rev().map(|component| component.parse::<u64>());
+        let seconds = values.next()?.ok()?;
+        let minutes = values.next()?.ok()?;
+        let hours = values.next().transpose().ok()?.unwrap_or_default();
+        if seconds >= 60 || (components.len() == 3 && minutes >= 60) {
+            return None;
+        }
+        hours.checked_mul(3600)?.checked_add(minutes.checked_mul(60)?)?.checked_add(seconds)
+    }
+}
+
+/// The markup language of a description
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Markup {
+    /// Plain text
+    #[default]
+    Plain,
+    /// CommonMark Markdown
+    Markdown,
+}
+impl Markup {
+    /// Whether the markup is plain text
+    pub fn is_plain(&self) -> bool {
+        *self == Self::Plain
+    }
 }
 
 /// A playlist
@@ -39,3 +134,32 @@ pub struct Playlist {
     /// The URL to the show
     pub url: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(Chapter::parse_timestamp("00:00"), Some(0));
+        assert_eq!(Chapter::parse_timestamp("1:02"), Some(62));
+        assert_eq!(Chapter::parse_timestamp("90:00"), Some(5400));
+        assert_eq!(Chapter::parse_timestamp("1:02:03"), Some(3723));
+        assert_eq!(Chapter::parse_timestamp("1:60"), None);
+        assert_eq!(Chapter::parse_timestamp("1:60:00"), None);
+        assert_eq!(Chapter::parse_timestamp("1:2:3:4"), None);
+        assert_eq!(Chapter::parse_timestamp("1:-2"), None);
+    }
+
+    #[test]
+    fn timestamp_overflow() {
+        assert_eq!(Chapter::parse_timestamp("307445734561825861:00"), None);
+        assert_eq!(Chapter::parse_timestamp("5124095576030432:00:00"), None);
+        assert_eq!(Chapter::parse_timestamp("99999999999999999999:00"), None);
+
+        // A description with such a timestamp must not panic
+        let description = "307445734561825861:00 x\n0:00 Intro\n1:00 Middle\n2:00 End";
+        let chapters = Chapter::from_description(description);
+        assert!(chapters.iter().all(|chapter| chapter.start <= 120));
+    }
+}
@@ -0,0 +1,35 @@
+//! MIME types of the files within a playlist directory
+
+/// The MIME type of files with an unknown extension
+pub const UNKNOWN: &str = "application/octet-stream";
+
+/// Gets the MIME type for a file extension; the extension is matched case-insensitively
+pub fn for_extension(extension: &str) -> &'static str {
+    match extension.to_ascii_lowercase().as_str() {
+        // Media files
+        "mp4" | "m4v" => "video/mp4",
+        "webm" => "video/webm",
+        "mkv" => "video/x-matroska",
+        "m4a" => "audio/mp4",
+        "mp3" => "audio/mpeg",
+        "aac" => "audio/aac",
+        "ogg" | "opus" => "audio/ogg",
+        "flac" => "audio/flac",
+        "wav" => "audio/wav",
+
+        // Thumbnails
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "webp" => "image/webp",
+
+        // Feeds, playlists and pages
+        "rss" => "application/rss+xml",
+        "opml" => "text/x-opml",
+        "xspf" => "application/xspf+xml",
+        "m3u8" => "application/vnd.apple.mpegurl",
+        "html" | "htm" => "text/html; charset=utf-8",
+        "json" | "feedme" => "application/json",
+        "txt" => "text/plain; charset=utf-8",
+        _ => UNKNOWN,
+    }
+}
@@ -0,0 +1,96 @@
+//! Watches directories for file changes
+
+use crate::error::Error;
+use std::path::Path;
+
+/// The quiet period to wait for further changes before reporting a batch of changes
+#[cfg(target_os = "linux")]
+const DEBOUNCE: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// A directory watcher based on inotify
+#[derive(Debug)]
+pub struct Watcher {
+    /// The inotify handle
+    #[cfg(target_os = "linux")]
+    inotify: inotify::Inotify,
+}
+impl Watcher {
+    /// Creates a new watcher for the given directories
+    #[cfg(target_os = "linux")]
+    pub fn new<I, P>(dirs: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        use inotify::{Inotify, WatchMask};
+
+        // Register the directories
+        let inotify = Inotify::init()?;
+        let mask = WatchMask::CREATE
+            | WatchMask::CLOSE_WRITE
+            | WatchMask::MOVED_TO
+            | WatchMask::MOVED_FROM
+            | WatchMask::DELETE;
+        for dir in dirs {
+            inotify.watches().add(dir, mask)?;
+        }
+        Ok(Self { inotify })
+    }
+    /// Creates a new watcher for the given directories
+    #[cfg(not(target_os = "linux"))]
+    pub fn new<I, P>(_dirs: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        Err(crate::error!("watching for file changes is only supported on Linux"))
+    }
+
+    /// Blocks until a file whose name matches `filter` has changed and no further changes happened for a short time
+    ///
+    /// Created, moved or deleted subdirectories are always considered a change.
+    #[cfg(target_os = "linux")]
+    pub fn wait<F>(&mut self, filter: F) -> Result<(), Error>
+    where
+        F: Fn(&str) -> bool,
+    {
+        use inotify::EventMask;
+        use std::io::ErrorKind;
+        use std::thread;
+
+        // Wait for a matching change
+        let mut buffer = [0; 4096];
+        'wait_change: loop {
+            let mut events = self.inotify.read_events_blocking(&mut buffer)?;
+            let is_change = events.any(|event| match event.name {
+                _ if event.mask.contains(EventMask::ISDIR) => true,
+                Some(name) => name.to_str().is_some_and(&filter),
+                None => false,
+            });
+            if is_change {
+                break 'wait_change;
+            }
+        }
+
+        // Drain all events until the directory has settled
+        'debounce: loop {
+            thread::sleep(DEBOUNCE);
+            match self.inotify.read_events(&mut buffer) {
+                Ok(_) => continue 'debounce,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break 'debounce,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+    /// Blocks until a file whose name matches `filter` has changed and no further changes happened for a short time
+    ///
+    /// Created, moved or deleted subdirectories are always considered a change.
+    #[cfg(not(target_os = "linux"))]
+    pub fn wait<F>(&mut self, _filter: F) -> Result<(), Error>
+    where
+        F: Fn(&str) -> bool,
+    {
+        Err(crate::error!("watching for file changes is only supported on Linux"))
+    }
+}
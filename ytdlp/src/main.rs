@@ -2,7 +2,7 @@
 
 mod ytdlp;
 
-use feedme_shared::{error, Error};
+use feedme_shared::{error, Error, Watcher};
 use std::{env, process};
 
 /// Displays the error and exits with status `2`
@@ -24,14 +24,29 @@ fn exit_error(e: Error) -> ! {
 
 /// The fallible, real main function
 fn main_real() -> Result<(), Error> {
-    // Get the argument and skip argv[0]
-    let mut args = env::args().skip(1);
-    if let Some(arg) = args.next() {
-        return Err(error!("unexpected argument: {arg}"));
+    // Get the arguments and skip argv[0]
+    let (mut watch, mut title) = (false, None);
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--watch" => watch = true,
+            _ if arg.starts_with("--title=") => title = arg.strip_prefix("--title=").map(str::to_string),
+            _ => return Err(error!("unexpected argument: {arg}")),
+        }
     }
 
     // Canonicalize metadata
-    ytdlp::canonicalize_meta()
+    if !watch {
+        return ytdlp::canonicalize_meta(title.as_deref());
+    }
+
+    // Canonicalize metadata whenever new files arrive
+    let mut watcher = Watcher::new(["."])?;
+    loop {
+        if let Err(e) = ytdlp::canonicalize_meta(title.as_deref()) {
+            eprintln!("[feedme-ytdlp] Failed to canonicalize metadata: {e}");
+        }
+        watcher.wait(ytdlp::is_source_file)?;
+    }
 }
 
 fn main() {
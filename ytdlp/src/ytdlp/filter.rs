@@ -0,0 +1,239 @@
+//! Configurable cleanup filters for video descriptions
+
+use feedme_shared::{error, Error};
+use regex::Regex;
+use serde::Deserialize;
+use std::fs;
+use std::io::ErrorKind;
+
+/// The name of the per-playlist filter configuration file
+pub const DESCRIPTION_FILTERS: &str = "description-filters.feedme";
+
+/// The serialized filter configuration
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    /// Regular expressions whose matches are removed
+    remove: Vec<String>,
+    /// Marker lines; the description is truncated before the first line that starts with one of them
+    truncate_at: Vec<String>,
+    /// Whether to remove hashtags
+    strip_hashtags: bool,
+    /// The maximum length of the description in characters
+    max_length: Option<usize>,
+}
+
+/// A description filter pipeline
+///
+/// The filters are applied in order: regex removals, truncation at marker lines, hashtag removal, and finally the
+/// length limit.
+#[derive(Debug, Clone, Default)]
+pub struct DescriptionFilter {
+    /// Regular expressions whose matches are removed
+    remove: Vec<Regex>,
+    /// Marker lines; the description is truncated before the first line that starts with one of them
+    truncate_at: Vec<String>,
+    /// Whether to remove hashtags
+    strip_hashtags: bool,
+    /// The maximum length of the description in characters
+    max_length: Option<usize>,
+}
+impl DescriptionFilter {
+    /// Loads the filters from the configuration file in the current working directory; a missing file disables all
+    /// filters
+    pub fn load() -> Result<Self, Error> {
+        // Read the configuration
+        let config = match fs::read(DESCRIPTION_FILTERS) {
+            Ok(config) => config,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(error!(with: e, r#"Failed to open "{DESCRIPTION_FILTERS}""#)),
+        };
+        Self::parse(&config)
+    }
+
+    /// Parses the filters from a serialized configuration
+    fn parse(config: &[u8]) -> Result<Self, Error> {
+        // Parse and validate the configuration
+        let config: Config = serde_json::from_slice(config)
+            .map_err(|e| error!(with: e, r#"Invalid description filters in "{DESCRIPTION_FILTERS}""#))?;
+        if config.max_length == Some(0) {
+            return Err(error!(
+                r#"Invalid description filters in "{DESCRIPTION_FILTERS}": max_length must not be zero"#
+            ));
+        }
+
+        // Compile the regular expressions
+        let mut remove = Vec::new();
+        for pattern in config.remove {
+            let regex = Regex::new(&pattern).map_err(|e| error!(with: e, "Invalid description filter: {pattern}"))?;
+            remove.push(regex);
+        }
+        Ok(Self {
+            remove,
+            truncate_at: config.truncate_at,
+            strip_hashtags: config.strip_hashtags,
+            max_length: config.max_length,
+        })
+    }
+
+    /// Whether no filter is configured
+    pub fn is_disabled(&self) -> bool {
+        self.remove.is_empty() && self.truncate_at.is_empty() && !self.strip_hashtags && self.max_length.is_none()
+    }
+
+    /// Applies the filters to the description; the description is returned unchanged if no filter is configured
+    pub fn apply(&self, description: &str) -> String {
+        // Keep the description as-is if there is nothing to do
+        if self.is_disabled() {
+            return description.to_string();
+        }
+
+        // Remove the matches of the regular expressions
+        let mut description = description.to_string();
+        for regex in &self.remove {
+            description = regex.replace_all(&description, "").into_owned();
+        }
+
+        // Truncate the description before the first marker line
+        let mut lines = Vec::new();
+        'lines: for line in description.lines() {
+            if self.truncate_at.iter().any(|marker| line.trim_start().starts_with(marker.as_str())) {
+                break 'lines;
+            }
+            lines.push(line);
+        }
+
+        // Remove the hashtags and collapse consecutive blank lines
+        let mut filtered = String::new();
+        'lines: for line in lines {
+            // Strip the hashtags from the line and drop lines that only consisted of hashtags
+            let stripped = match self.strip_hashtags {
+                true => line.split(' ').filter(|word| !is_hashtag(word)).collect::<Vec<_>>().join(" "),
+                false => line.to_string(),
+            };
+            if stripped.trim().is_empty() && !line.trim().is_empty() {
+                continue 'lines;
+            }
+            let line = stripped;
+
+            // Append the line unless it continues a blank section
+            let line = line.trim_end();
+            if !line.is_empty() || !(filtered.is_empty() || filtered.ends_with("\n\n")) {
+                filtered.push_str(line);
+                filtered.push('\n');
+            }
+        }
+        let filtered = filtered.trim_end();
+
+        // Limit the length
+        match self.max_length {
+            Some(max_length) => truncate(filtered, max_length),
+            None => filtered.to_string(),
+        }
+    }
+}
+
+/// Whether the word is a hashtag; like on YouTube, a hashtag must contain at least one letter, so `#1` is not a hashtag
+fn is_hashtag(word: &str) -> bool {
+    let Some(tag) = word.strip_prefix('#') else {
+        return false;
+    };
+    let is_word = tag.chars().all(|char| char.is_alphanumeric() || char == '_');
+    is_word && tag.chars().any(char::is_alphabetic)
+}
+
+/// Truncates the text to at most `max_length` characters, preferably at a word boundary, and marks the truncation with
+/// an ellipsis
+fn truncate(text: &str, max_length: usize) -> String {
+    // Check if the text needs to be truncated
+    if text.chars().count() <= max_length {
+        return text.to_string();
+    }
+
+    // Cut the text and leave room for the ellipsis
+    let cut: String = text.chars().take(max_length.saturating_sub(1)).collect();
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(boundary) if boundary > cut.len() / 2 => &cut[..boundary],
+        _ => cut.as_str(),
+    };
+    format!("{}…", cut.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses the filter configuration
+    fn filter(config: &str) -> DescriptionFilter {
+        DescriptionFilter::parse(config.as_bytes()).expect("invalid filter configuration")
+    }
+
+    #[test]
+    fn disabled() {
+        let filter = filter("{}");
+        assert!(filter.is_disabled());
+        assert_eq!(filter.apply("Keep   this\n\n\n#as is "), "Keep   this\n\n\n#as is ");
+    }
+
+    #[test]
+    fn regex_removal() {
+        let filter =
+            filter(r#"{ "remove": ["(?i)this video is sponsored by \\w+\\.", "https://shop\\.example\\S*"] }"#);
+        let description = "Welcome! This video is sponsored by Foo.\nMerch: https://shop.example/merch\nBye";
+        assert_eq!(filter.apply(description), "Welcome!\nMerch:\nBye");
+    }
+
+    #[test]
+    fn marker_truncation() {
+        let filter = filter(r#"{ "truncate_at": ["-----", "Follow us"] }"#);
+        let description = "About this episode\n\nMore details\n   Follow us on Mastodon\nhttps://example.org";
+        assert_eq!(filter.apply(description), "About this episode\n\nMore details");
+        assert_eq!(filter.apply("Intro\n-----\nLinks"), "Intro");
+
+        // Markers within a line do not truncate
+        assert_eq!(filter.apply("Please Follow us"), "Please Follow us");
+    }
+
+    #[test]
+    fn hashtag_stripping() {
+        let filter = filter(r#"{ "strip_hashtags": true }"#);
+        assert_eq!(filter.apply("New episode #podcast #rust_lang #2024edition"), "New episode");
+        assert_eq!(filter.apply("Über #größe #日本語 #Ünïcödé tags"), "Über tags");
+
+        // Numbers, lone hashes and words with punctuation are no hashtags
+        assert_eq!(filter.apply("Episode #1 of #2024 # #c++ #a-b"), "Episode #1 of #2024 # #c++ #a-b");
+
+        // Lines that only contain hashtags disappear
+        assert_eq!(filter.apply("Text\n#tag #tag2\nMore"), "Text\nMore");
+    }
+
+    #[test]
+    fn blank_line_collapsing() {
+        let filter = filter(r#"{ "strip_hashtags": true }"#);
+        assert_eq!(filter.apply("\n\nFirst\n\n\n\nSecond  \n \n\t\nThird\n\n"), "First\n\nSecond\n\nThird");
+        assert_eq!(filter.apply("First\n#only #tags\n\nSecond"), "First\n\nSecond");
+    }
+
+    #[test]
+    fn max_length() {
+        let filter = filter(r#"{ "max_length": 12 }"#);
+        assert_eq!(filter.apply("Short text"), "Short text");
+        assert_eq!(filter.apply("A longer description"), "A longer…");
+    }
+
+    #[test]
+    fn max_length_zero() {
+        let error = DescriptionFilter::parse(br#"{ "max_length": 0 }"#).expect_err("zero max_length was accepted");
+        assert!(error.to_string().contains("max_length"));
+    }
+
+    #[test]
+    fn truncate_multibyte() {
+        // The cut must happen at character boundaries, not at byte offsets
+        assert_eq!(truncate("ÄÖÜ äöü ß", 9), "ÄÖÜ äöü ß");
+        assert_eq!(truncate("ÄÖÜ äöü ß", 8), "ÄÖÜ äöü…");
+        assert_eq!(truncate("ÄÖÜ äöü ß", 5), "ÄÖÜ…");
+        assert_eq!(truncate("☃☃☃☃☃☃", 3), "☃☃…");
+        assert_eq!(truncate("日本語のテキスト", 1), "…");
+    }
+}
@@ -1,9 +1,17 @@
 //! Metadata schemas
+//!
+//! Apart from the video ID, all fields are optional since their availability depends on the extractor (e.g. YouTube,
+//! Vimeo, SoundCloud, Bandcamp or the generic extractor) and the video (e.g. live streams have no duration); missing
+//! fields are replaced by the documented fallbacks.
 
 use crate::error;
 use crate::error::Error;
+use feedme_shared::{mime, Chapter};
 use serde::Deserialize;
 use serde_json::Value;
+use time::format_description::FormatItem;
+use time::macros::format_description;
+use time::Date;
 
 /// A playlist thumbnail item
 #[derive(Debug, Clone, Deserialize)]
@@ -12,44 +20,219 @@ pub struct Thumbnail {
     /// The thumbnail URL
     pub url: String,
     /// The thumbnail heigth
-    pub height: u64,
+    pub height: Option<u64>,
     /// The thumbnail width
-    pub width: u64,
+    pub width: Option<u64>,
 }
 
 /// Playlist metadata
 #[derive(Debug, Clone, Deserialize)]
 #[allow(unused)]
 pub struct PlaylistMeta {
+    /// The playlist ID
+    pub id: Option<String>,
     /// The human readable playlist title
-    pub title: String,
+    pub title: Option<String>,
     /// The playlist description
-    pub description: String,
+    pub description: Option<String>,
     /// The playlist uploader
-    pub uploader: String,
+    pub uploader: Option<String>,
+    /// The channel name
+    pub channel: Option<String>,
+    /// The uploader ID
+    pub uploader_id: Option<String>,
     /// The playlist thumbnails
-    pub thumbnails: Vec<Thumbnail>,
+    pub thumbnails: Option<Vec<Thumbnail>>,
     /// The webpage URL
-    pub webpage_url: String,
+    pub webpage_url: Option<String>,
+    /// The URL that was passed to yt-dlp
+    pub original_url: Option<String>,
+}
+impl PlaylistMeta {
+    /// The title of a playlist without title and ID
+    const UNTITLED: &'static str = "Untitled playlist";
+
+    /// The human readable playlist title; falls back to the playlist ID or `Untitled playlist`
+    pub fn title(&self) -> String {
+        let title = self.title.as_ref().or(self.id.as_ref());
+        title.cloned().unwrap_or_else(|| Self::UNTITLED.to_string())
+    }
+
+    /// The playlist author; falls back to the channel name or the uploader ID
+    pub fn author(&self) -> Option<String> {
+        let author = self.uploader.as_ref().or(self.channel.as_ref()).or(self.uploader_id.as_ref());
+        author.cloned()
+    }
+
+    /// The playlist URL; falls back to the URL that was passed to yt-dlp
+    pub fn url(&self) -> Option<String> {
+        self.webpage_url.as_ref().or(self.original_url.as_ref()).cloned()
+    }
+
+    /// Describes the missing fields that are replaced by fallbacks
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.title.is_none() {
+            warnings.push(format!("missing title, using {:?}", self.title()));
+        }
+        warnings
+    }
+}
+
+/// A chapter marker
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChapterMeta {
+    /// The chapter start in seconds
+    pub start_time: f64,
+    /// The human readable chapter title
+    #[serde(default)]
+    pub title: String,
 }
 
 /// A playlist entry metadata
 #[derive(Debug, Clone, Deserialize)]
 pub struct EntryMeta {
-    /// The youtube video ID
+    /// The extractor-specific video ID
     pub id: String,
+    /// The name of the extractor (e.g. `youtube` or `vimeo`)
+    pub extractor: Option<String>,
+    /// The class name of the extractor (e.g. `Youtube` or `Vimeo`)
+    pub extractor_key: Option<String>,
     /// The extension of the video file
-    pub ext: String,
+    pub ext: Option<String>,
     /// The human readable video title
-    pub title: String,
+    pub title: Option<String>,
+    /// The track title of music extractors
+    pub track: Option<String>,
+    /// The track artist of music extractors
+    pub artist: Option<String>,
+    /// The uploader name
+    pub uploader: Option<String>,
+    /// The uploader ID
+    pub uploader_id: Option<String>,
+    /// The channel name
+    pub channel: Option<String>,
     /// The video description
-    pub description: String,
-    /// The video duration in seconds
-    pub duration: u64,
+    pub description: Option<String>,
+    /// The video duration in seconds; some extractors report fractional seconds
+    pub duration: Option<f64>,
     /// The upload date in `YYYYMMdd`
-    pub upload_date: String,
-    /// The index within the playlist (starting with 1)
-    pub playlist_index: u64,
+    pub upload_date: Option<String>,
+    /// The upload time as unix timestamp; some extractors report fractional seconds
+    pub timestamp: Option<f64>,
+    /// The release date in `YYYYMMdd` (e.g. for music or premieres)
+    pub release_date: Option<String>,
+    /// The release time as unix timestamp
+    pub release_timestamp: Option<f64>,
+    /// The index within the playlist (starting with 1) if the video was downloaded as part of a playlist
+    pub playlist_index: Option<u64>,
+    /// The chapter markers if any
+    pub chapters: Option<Vec<ChapterMeta>>,
+}
+impl EntryMeta {
+    /// The extension of a video without extension
+    const DEFAULT_EXT: &'static str = "mp4";
+
+    /// The extension of the video file; falls back to `mp4`
+    pub fn ext(&self) -> &str {
+        self.ext.as_deref().unwrap_or(Self::DEFAULT_EXT)
+    }
+
+    /// The human readable video title; falls back to `<artist> - <track>`, the track title or the video ID
+    pub fn title(&self) -> String {
+        match (&self.title, &self.artist, &self.track) {
+            (Some(title), _, _) => title.clone(),
+            (None, Some(artist), Some(track)) => format!("{artist} - {track}"),
+            (None, None, Some(track)) => track.clone(),
+            _ => self.id.clone(),
+        }
+    }
+
+    /// The video author; falls back to the channel name, the artist or the uploader ID
+    pub fn author(&self) -> Option<String> {
+        let author = self.uploader.as_ref().or(self.channel.as_ref()).or(self.artist.as_ref());
+        author.or(self.uploader_id.as_ref()).cloned()
+    }
+
+    /// The name of the extractor; falls back to the class name of the extractor
+    pub fn extractor(&self) -> Option<String> {
+        self.extractor.as_ref().or(self.extractor_key.as_ref()).cloned()
+    }
+
+    /// The MIME type of the video file, derived from its extension
+    pub fn mime_type(&self) -> &'static str {
+        mime::for_extension(self.ext())
+    }
+
+    /// The video description; falls back to an empty description
+    pub fn description(&self) -> &str {
+        self.description.as_deref().unwrap_or_default()
+    }
+
+    /// The video duration in whole seconds; falls back to `0`
+    pub fn duration(&self) -> u64 {
+        self.duration.map(|duration| duration.round() as u64).unwrap_or_default()
+    }
+
+    /// The upload date as unix timestamp; falls back to the `timestamp`, the `release_date` and the
+    /// `release_timestamp`, or `None` if all of them are missing
+    pub fn upload_date(&self) -> Result<Option<u64>, Error> {
+        // Use the first available date
+        let date = match (&self.upload_date, self.timestamp, &self.release_date, self.release_timestamp) {
+            (Some(upload_date), _, _, _) => Some(parse_date(upload_date)?),
+            (None, Some(timestamp), _, _) => Some(timestamp as u64),
+            (None, None, Some(release_date), _) => Some(parse_date(release_date)?),
+            (None, None, None, release_timestamp) => release_timestamp.map(|timestamp| timestamp as u64),
+        };
+        Ok(date)
+    }
+
+    /// The upload time as unix timestamp if known; falls back to the `release_timestamp`
+    pub fn timestamp(&self) -> Option<u64> {
+        let timestamp = self.timestamp.or(self.release_timestamp);
+        timestamp.map(|timestamp| timestamp as u64)
+    }
+
+    /// The chapter markers; falls back to the chapters listed in the description
+    pub fn chapters(&self) -> Vec<Chapter> {
+        match &self.chapters {
+            Some(chapters) if !chapters.is_empty() => chapters
+                .iter()
+                .map(|chapter| Chapter { start: chapter.start_time as u64, title: chapter.title.clone() })
+                .collect(),
+            _ => Chapter::from_description(self.description()),
+        }
+    }
+
+    /// Describes the missing fields that are replaced by fallbacks
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.ext.is_none() {
+            warnings.push(format!("missing extension, assuming {:?}", Self::DEFAULT_EXT));
+        }
+        if self.title.is_none() {
+            warnings.push(format!("missing title, using {:?}", self.title()));
+        }
+        if self.duration.is_none() {
+            warnings.push("missing duration, assuming 0".to_string());
+        }
+        let dates = (&self.upload_date, self.timestamp, &self.release_date, self.release_timestamp);
+        if let (None, None, None, None) = dates {
+            warnings.push("missing upload date, using the file modification time".to_string());
+        }
+        warnings
+    }
+}
+
+/// Parses a `YYYYMMdd` date into a unix timestamp
+fn parse_date(date: &str) -> Result<u64, Error> {
+    /// The date format within the metadata
+    const DATE_FORMAT: &[FormatItem] = format_description!("[year][month][day]");
+
+    // Parse the date
+    let date_ = Date::parse(date, DATE_FORMAT).map_err(|e| error!(with: e, "invalid date: {date}"))?;
+    let date_unix = date_.midnight().assume_utc().unix_timestamp();
+    u64::try_from(date_unix).map_err(|e| error!(with: e, "timestamp is too large"))
 }
 
 /// Some metadata
@@ -67,13 +250,13 @@ impl TryFrom<Value> for Meta {
         /// A helper struct to extract the `_type` field
         #[derive(Debug, Deserialize)]
         struct TypeInfo {
-            /// The `_type` field
-            pub _type: String,
+            /// The `_type` field; metadata without type is treated as video
+            pub _type: Option<String>,
         }
 
         // Deserialize the value
         let type_info: TypeInfo = serde_json::from_value(value.clone())?;
-        match type_info._type.as_str() {
+        match type_info._type.as_deref().unwrap_or("video") {
             "playlist" => {
                 let playlist_meta: PlaylistMeta = serde_json::from_value(value)?;
                 Ok(Self::Playlist(playlist_meta))
@@ -86,3 +269,172 @@ impl TryFrom<Value> for Meta {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses an info.json fixture
+    fn fixture(json: &str) -> Meta {
+        let json: Value = serde_json::from_str(json).expect("invalid fixture");
+        Meta::try_from(json).expect("failed to parse fixture")
+    }
+
+    /// Parses an info.json fixture that describes a video
+    fn entry_fixture(json: &str) -> EntryMeta {
+        match fixture(json) {
+            Meta::Entry(entry) => entry,
+            Meta::Playlist(_) => panic!("fixture is not a video"),
+        }
+    }
+
+    /// Parses an info.json fixture that describes a playlist
+    fn playlist_fixture(json: &str) -> PlaylistMeta {
+        match fixture(json) {
+            Meta::Playlist(playlist) => playlist,
+            Meta::Entry(_) => panic!("fixture is not a playlist"),
+        }
+    }
+
+    #[test]
+    fn youtube_video() {
+        let entry = entry_fixture(include_str!("../../tests/fixtures/youtube-video.info.json"));
+        assert_eq!(entry.ext(), "mp4");
+        assert_eq!(entry.title(), "Rick Astley - Never Gonna Give You Up (Official Music Video)");
+        assert_eq!(entry.duration(), 212);
+        assert_eq!(entry.upload_date().expect("invalid upload date"), Some(1256428800));
+        assert_eq!(entry.timestamp(), Some(1256453863));
+        assert_eq!(entry.playlist_index, Some(3));
+        assert_eq!(entry.chapters().iter().map(|chapter| chapter.start).collect::<Vec<_>>(), [0, 18, 43]);
+        assert_eq!(entry.author().as_deref(), Some("Rick Astley"));
+        assert_eq!(entry.extractor().as_deref(), Some("youtube"));
+        assert_eq!(entry.mime_type(), "video/mp4");
+        assert!(entry.warnings().is_empty());
+    }
+
+    #[test]
+    fn youtube_playlist() {
+        let playlist = playlist_fixture(include_str!("../../tests/fixtures/youtube-playlist.info.json"));
+        assert_eq!(playlist.title(), "Rick Astley Hits");
+        assert_eq!(playlist.author().as_deref(), Some("Rick Astley"));
+        assert_eq!(
+            playlist.url().as_deref(),
+            Some("https://www.youtube.com/playlist?list=PLFgquLnL59alCl_2TQvOiD5Vgm1hCaGSI")
+        );
+        assert_eq!(playlist.thumbnails.as_ref().map(Vec::len), Some(2));
+        assert!(playlist.warnings().is_empty());
+    }
+
+    #[test]
+    fn youtube_channel_tab() {
+        let playlist = playlist_fixture(include_str!("../../tests/fixtures/youtube-channel-tab.info.json"));
+        assert_eq!(playlist.title(), "UCuAXFkgsw1L7xaCfnd5JJOw");
+        assert_eq!(playlist.description, None);
+        assert_eq!(playlist.author().as_deref(), Some("Rick Astley"));
+        assert_eq!(playlist.warnings().len(), 1);
+    }
+
+    #[test]
+    fn youtube_channel_video() {
+        let entry = entry_fixture(include_str!("../../tests/fixtures/youtube-channel-video.info.json"));
+        assert_eq!(entry.author().as_deref(), Some("Philipp Hagemeister"));
+        assert_eq!(entry.mime_type(), "video/webm");
+        assert_eq!(entry.playlist_index, None);
+        assert_eq!(entry.timestamp(), None);
+        assert!(entry.warnings().is_empty());
+    }
+
+    #[test]
+    fn youtube_live() {
+        let entry = entry_fixture(include_str!("../../tests/fixtures/youtube-live.info.json"));
+        assert_eq!(entry.duration(), 0);
+        assert_eq!(entry.playlist_index, None);
+        assert_eq!(entry.upload_date().expect("invalid upload date"), Some(1657324800));
+        assert!(entry.chapters().is_empty());
+        assert_eq!(entry.warnings(), ["missing duration, assuming 0"]);
+    }
+
+    #[test]
+    fn youtube_minimal() {
+        let entry = entry_fixture(include_str!("../../tests/fixtures/youtube-minimal.info.json"));
+        assert_eq!(entry.ext(), "mp4");
+        assert_eq!(entry.title(), "xxxxxxxxxxx");
+        assert_eq!(entry.description(), "");
+        assert_eq!(entry.duration(), 0);
+        assert_eq!(entry.upload_date().expect("invalid upload date"), None);
+        assert_eq!(entry.warnings().len(), 4);
+    }
+
+    #[test]
+    fn vimeo_video() {
+        let entry = entry_fixture(include_str!("../../tests/fixtures/vimeo-video.info.json"));
+        assert_eq!(entry.duration(), 62);
+        assert_eq!(entry.upload_date().expect("invalid upload date"), Some(1381795200));
+        assert_eq!(entry.timestamp(), Some(1381846109));
+        assert_eq!(entry.author().as_deref(), Some("Vimeo"));
+        assert_eq!(entry.extractor().as_deref(), Some("vimeo"));
+        assert_eq!(entry.mime_type(), "video/mp4");
+        assert!(entry.warnings().is_empty());
+    }
+
+    #[test]
+    fn soundcloud_track() {
+        let entry = entry_fixture(include_str!("../../tests/fixtures/soundcloud-track.info.json"));
+        assert_eq!(entry.ext(), "mp3");
+        assert_eq!(entry.duration(), 143);
+        assert_eq!(entry.description(), "");
+        assert_eq!(entry.upload_date().expect("invalid upload date"), Some(1349920598));
+        assert_eq!(entry.author().as_deref(), Some("E.T. ExTerrestrial Music"));
+        assert_eq!(entry.extractor().as_deref(), Some("soundcloud"));
+        assert_eq!(entry.mime_type(), "audio/mpeg");
+        assert!(entry.warnings().is_empty());
+    }
+
+    #[test]
+    fn bandcamp_track() {
+        let entry = entry_fixture(include_str!("../../tests/fixtures/bandcamp-track.info.json"));
+        assert_eq!(entry.duration(), 10);
+        assert_eq!(entry.upload_date().expect("invalid release date"), Some(1354147200));
+        assert_eq!(entry.timestamp(), None);
+        assert_eq!(entry.author().as_deref(), Some("youtube-dl  \"'/\\ä↭"));
+        assert_eq!(entry.extractor().as_deref(), Some("Bandcamp"));
+        assert_eq!(entry.mime_type(), "audio/mpeg");
+        assert!(entry.warnings().is_empty());
+    }
+
+    #[test]
+    fn bandcamp_untitled() {
+        let entry = entry_fixture(include_str!("../../tests/fixtures/bandcamp-untitled.info.json"));
+        assert_eq!(entry.title(), "Ben Prunty - Lanius (Battle)");
+        assert_eq!(entry.upload_date().expect("invalid release date"), Some(1396915200));
+        assert_eq!(entry.timestamp(), Some(1396915200));
+        assert_eq!(entry.author().as_deref(), Some("Ben Prunty"));
+        assert_eq!(entry.extractor().as_deref(), Some("Bandcamp"));
+        assert_eq!(entry.mime_type(), "audio/mp4");
+        assert_eq!(entry.warnings().len(), 1);
+    }
+
+    #[test]
+    fn generic_direct() {
+        let entry = entry_fixture(include_str!("../../tests/fixtures/generic-direct.info.json"));
+        assert_eq!(entry.title(), "episode-42");
+        assert_eq!(entry.upload_date().expect("invalid upload date"), Some(1699142400));
+        assert_eq!(entry.timestamp(), Some(1699178400));
+        assert_eq!(entry.author(), None);
+        assert_eq!(entry.extractor().as_deref(), Some("generic"));
+        assert_eq!(entry.mime_type(), "audio/mpeg");
+        assert_eq!(entry.warnings(), ["missing duration, assuming 0"]);
+    }
+
+    #[test]
+    fn invalid_upload_date() {
+        let entry = entry_fixture(r#"{"_type": "video", "id": "x", "upload_date": "2024-01-01"}"#);
+        assert!(entry.upload_date().is_err());
+    }
+
+    #[test]
+    fn unknown_type() {
+        let json = serde_json::json!({ "_type": "url", "id": "x", "url": "https://example.org" });
+        assert!(Meta::try_from(json).is_err());
+    }
+}
@@ -1,32 +1,78 @@
 //! A `yt-dlp` interface
 
+mod filter;
 mod meta;
 
+use crate::ytdlp::filter::DescriptionFilter;
 use crate::ytdlp::meta::{EntryMeta, Meta, PlaylistMeta};
-use feedme_shared::{error, Entry, Error, Playlist, UuidBuilder};
+use feedme_shared::{error, Entry, Error, Markup, Playlist, UuidBuilder};
 use serde_json::Value;
-use std::collections::BTreeMap;
-use std::fs;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, Metadata};
 use std::path::Path;
-use time::format_description::FormatItem;
-use time::macros::format_description;
-use time::Date;
+use std::time::UNIX_EPOCH;
+
+/// The name of the playlist metadata file
+const PLAYLIST_META: &str = "playlist-meta.feedme";
 
 /// Canonicalizes the metadata
-pub fn canonicalize_meta() -> Result<(), Error> {
-    // Process all metadata
-    for (name, meta) in collect_metadata()? {
+///
+/// If there is no playlist metadata (e.g. for individually downloaded videos), a playlist with the given `title` is
+/// synthesized instead unless the playlist metadata file already exists.
+pub fn canonicalize_meta(title: Option<&str>) -> Result<(), Error> {
+    // Load the description filters and the metadata
+    let filter = DescriptionFilter::load()?;
+    let metadata = collect_metadata()?;
+
+    // Synthesize the playlist if necessary without overwriting an existing (e.g. hand-edited) one; the author is only
+    // known if all videos share the same author
+    let has_playlist =
+        metadata.values().any(|meta| matches!(meta, Meta::Playlist(_))) || Path::new(PLAYLIST_META).exists();
+    if let (false, Some(title)) = (has_playlist, title) {
+        let authors: BTreeSet<_> = metadata
+            .values()
+            .filter_map(|meta| match meta {
+                Meta::Entry(meta) => Some(meta.author()),
+                Meta::Playlist(_) => None,
+            })
+            .collect();
+        let author = match authors.len() {
+            1 => authors.into_iter().next().flatten(),
+            _ => None,
+        };
+        synthesize_playlist_meta(title, author)?;
+    }
+
+    // Process all metadata; a broken file must not prevent the other files from being processed
+    for (name, meta) in metadata {
         // Select the appropriate translator
-        match meta {
-            Meta::Playlist(meta) => translate_playlist_meta(name, meta)?,
-            Meta::Entry(meta) => translate_entry_meta(name, meta)?,
+        let result = match meta {
+            Meta::Playlist(meta) => translate_playlist_meta(&name, meta),
+            Meta::Entry(meta) => translate_entry_meta(&name, meta, &filter),
+        };
+        if let Err(e) = result {
+            eprintln!("[feedme-ytdlp] Warning: Skipping {name}: {e}");
         }
     }
     Ok(())
 }
 
+/// Whether the file name references a file that is used as input to `canonicalize_meta`
+pub fn is_source_file(name: &str) -> bool {
+    // Ignore hidden files, our own output and incomplete downloads
+    let is_hidden = name.starts_with('.');
+    let is_output = name.ends_with(".feedme");
+    let is_partial = name.ends_with(".part") || name.ends_with(".ytdl") || name.contains(".part-Frag");
+    !is_hidden && !is_output && !is_partial
+}
+
 /// Translate a playlist metadata file
-fn translate_playlist_meta(name: String, meta: PlaylistMeta) -> Result<(), Error> {
+fn translate_playlist_meta(name: &str, meta: PlaylistMeta) -> Result<(), Error> {
+    // Report the fallbacks
+    for warning in meta.warnings() {
+        eprintln!("[feedme-ytdlp] Warning: {name}: {warning}");
+    }
+
     // Check if a thumbnail exists
     let basename = name.strip_suffix(".info.json").expect("invalid name of metadata file");
     let thumbnail_name = format!("{basename}.jpg");
@@ -37,55 +83,77 @@ fn translate_playlist_meta(name: String, meta: PlaylistMeta) -> Result<(), Error
 
     // Create the canonical representation
     let playlist = Playlist {
-        title: meta.title,
-        description: Some(meta.description),
-        author: Some(meta.uploader),
+        title: meta.title(),
+        author: meta.author(),
+        url: meta.url(),
+        description: meta.description,
         thumbnail: maybr_thumbnail,
-        url: Some(meta.webpage_url),
     };
 
     // Serialize and write the metadata
     let playlist_json = serde_json::to_string_pretty(&playlist)?;
-    fs::write("playlist-meta.feedme", playlist_json.as_bytes())?;
+    fs::write(PLAYLIST_META, playlist_json.as_bytes())?;
     Ok(())
 }
 
-/// Translates an entry metadata file
-fn translate_entry_meta(name: String, meta: EntryMeta) -> Result<(), Error> {
-    /// The date format within the metadata
-    const DATE_FORMAT: &[FormatItem] = format_description!("[year][month][day]");
+/// Writes the metadata of a playlist that only consists of the given title and author
+fn synthesize_playlist_meta(title: &str, author: Option<String>) -> Result<(), Error> {
+    // Create the canonical representation
+    let playlist = Playlist { title: title.to_string(), description: None, author, thumbnail: None, url: None };
 
+    // Serialize and write the metadata
+    let playlist_json = serde_json::to_string_pretty(&playlist)?;
+    fs::write(PLAYLIST_META, playlist_json.as_bytes())?;
+    Ok(())
+}
+
+/// Translates an entry metadata file
+fn translate_entry_meta(name: &str, meta: EntryMeta, filter: &DescriptionFilter) -> Result<(), Error> {
     // Build the video name
     let basename = name.strip_suffix(".info.json").expect("invalid name of metadata file");
-    let video_name = format!("{basename}.{}", meta.ext);
+    let video_name = format!("{basename}.{}", meta.ext());
 
-    // Check if the entry exists already
-    let entry_json_name = format!("playlist-entry{:05}.feedme", meta.playlist_index);
+    // Get the date, falling back to the modification time of the video file
+    let date = match meta.upload_date()? {
+        Some(date) => date,
+        None => video_meta(&video_name)?.modified()?.duration_since(UNIX_EPOCH)?.as_secs(),
+    };
+
+    // Check if the entry exists already; entries without playlist index are numbered by their upload time
+    let entry_json_name = match meta.playlist_index {
+        Some(playlist_index) => format!("playlist-entry{playlist_index:05}.feedme"),
+        None => dated_entry_name(Path::new("."), &video_name, meta.timestamp().unwrap_or(date))?,
+    };
     if Path::new(&entry_json_name).exists() {
         eprintln!("[feedme-ytdlp] Skipping existing entry: {video_name}");
         return Ok(());
     }
 
+    // Report the fallbacks
+    for warning in meta.warnings() {
+        eprintln!("[feedme-ytdlp] Warning: {name}: {warning}");
+    }
+
     // Get the file metadata and compute UUID
     eprintln!("[feedme-ytdlp] Computing UUID for: {video_name}");
-    let file_meta = fs::metadata(&video_name)?;
+    let file_meta = video_meta(&video_name)?;
     let file_uuid = UuidBuilder::new().context(&meta.id).finalize(&video_name)?;
 
-    // Parse the date
-    let date = Date::parse(&meta.upload_date, DATE_FORMAT)?;
-    let date_unix_i64 = date.midnight().assume_utc().unix_timestamp();
-    let date_unix = u64::try_from(date_unix_i64).map_err(|e| error!(with: e, "timestamp is too large"))?;
-
     // Build the entry
+    let description = filter.apply(meta.description());
     let entry = Entry {
+        title: meta.title(),
+        author: meta.author(),
+        extractor: meta.extractor(),
+        type_: meta.mime_type().to_string(),
+        description: Some(description).filter(|description| !description.is_empty()),
+        duration: meta.duration(),
+        chapters: meta.chapters(),
         file: video_name,
         size: file_meta.len(),
-        type_: "video/mp4".to_string(),
-        title: meta.title,
-        description: Some(meta.description),
         uuid: file_uuid,
-        duration: meta.duration,
-        date: date_unix,
+        date,
+        markup: Markup::Plain,
     };
 
     // Serialize and write the entry
@@ -94,6 +162,54 @@ fn translate_entry_meta(name: String, meta: EntryMeta) -> Result<(), Error> {
     Ok(())
 }
 
+/// Gets the file metadata of the video file
+fn video_meta(video_name: &str) -> Result<Metadata, Error> {
+    fs::metadata(video_name).map_err(|e| error!(with: e, r#"Failed to open "{video_name}""#))
+}
+
+/// Gets the name of the entry file for a video without playlist index within `dir`
+///
+/// The entry is numbered by its upload time so that the entries are ordered chronologically. The number is padded to ten
+/// digits, so that the lexical order of the names matches the numeric order for all upload times until the year 2286.
+/// If the number is already taken by another video, the next free number is used. An existing entry for the same video
+/// keeps its name even if the upload time has changed since (e.g. because it falls back to the modification time of the
+/// video file), so the name remains stable across runs.
+fn dated_entry_name(dir: &Path, video_name: &str, upload_time: u64) -> Result<String, Error> {
+    // Reuse the name of an existing entry for the same video
+    'readdir: for entry in fs::read_dir(dir)? {
+        // Unwrap the entry
+        let Ok(entry) = entry else {
+            continue 'readdir;
+        };
+
+        // Check if the entry is an entry file
+        let Ok(entry_json_name) = entry.file_name().into_string() else {
+            continue 'readdir;
+        };
+        if !entry_json_name.starts_with("playlist-entry") || !entry_json_name.ends_with(".feedme") {
+            continue 'readdir;
+        }
+
+        // Check if the entry belongs to the same video
+        let entry_json =
+            fs::read(entry.path()).map_err(|e| error!(with: e, r#"Failed to open "{entry_json_name}""#))?;
+        let entry: Entry = serde_json::from_slice(&entry_json)?;
+        if entry.file == video_name {
+            return Ok(entry_json_name);
+        }
+    }
+
+    // Use the first free number starting at the upload time
+    let mut number = upload_time;
+    loop {
+        let entry_json_name = format!("playlist-entry{number:010}.feedme");
+        if !dir.join(&entry_json_name).exists() {
+            return Ok(entry_json_name);
+        }
+        number = number.checked_add(1).ok_or(error!("no free entry number for: {video_name}"))?;
+    }
+}
+
 /// Reads and parses all `.info.json`-files from the current working directory
 fn collect_metadata() -> Result<BTreeMap<String, Meta>, Error> {
     // Enumerate entries
@@ -117,12 +233,67 @@ fn collect_metadata() -> Result<BTreeMap<String, Meta>, Error> {
 
         // Read and parse the entry
         eprintln!("[feedme-ytdlp] Ingesting: {}", entry.path().display());
-        let json_raw = fs::read(entry.path())?;
-        let json: Value = serde_json::from_slice(&json_raw)?;
-        let meta = Meta::try_from(json)?;
+        let meta = fs::read(entry.path()).map_err(Error::from).and_then(|json_raw| {
+            let json: Value = serde_json::from_slice(&json_raw)?;
+            Meta::try_from(json)
+        });
+        let meta = match meta {
+            Ok(meta) => meta,
+            Err(e) => {
+                eprintln!("[feedme-ytdlp] Warning: Skipping {entry_name}: {e}");
+                continue 'readdir;
+            }
+        };
 
         // Register the metadata
         entries.insert(entry_name, meta);
     }
     Ok(entries)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feedme_shared::Uuid;
+    use std::{env, process};
+
+    /// Writes an entry file for the given video into `dir`
+    fn write_entry(dir: &Path, entry_json_name: &str, video_name: &str) {
+        let entry = Entry {
+            file: video_name.to_string(),
+            uuid: Uuid { bytes: [0; 16] },
+            size: 0,
+            type_: "video/mp4".to_string(),
+            duration: 0,
+            date: 0,
+            title: video_name.to_string(),
+            description: None,
+            markup: Markup::Plain,
+            chapters: Vec::new(),
+            author: None,
+            extractor: None,
+        };
+        let entry_json = serde_json::to_vec(&entry).expect("failed to serialize entry");
+        fs::write(dir.join(entry_json_name), entry_json).expect("failed to write entry");
+    }
+
+    #[test]
+    fn dated_entry_names() {
+        // Create a playlist directory with an entry that was numbered by an earlier upload time
+        let dir = env::temp_dir().join(format!("feedme-test-dated-entry-names-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("failed to create temporary directory");
+        write_entry(&dir, "playlist-entry1792346633.feedme", "video.mp4");
+
+        // The existing entry is reused even if the upload time has changed
+        let name = dated_entry_name(&dir, "video.mp4", 1577836800);
+        assert_eq!(name.ok().as_deref(), Some("playlist-entry1792346633.feedme"));
+
+        // Other videos get a free number starting at their upload time
+        let name = dated_entry_name(&dir, "other.mp4", 1577836800);
+        assert_eq!(name.ok().as_deref(), Some("playlist-entry1577836800.feedme"));
+        let name = dated_entry_name(&dir, "other.mp4", 1792346633);
+        assert_eq!(name.ok().as_deref(), Some("playlist-entry1792346634.feedme"));
+        fs::remove_dir_all(&dir).expect("failed to remove temporary directory");
+    }
+}